@@ -1,124 +1,1044 @@
 extern crate html_escape;
 
-use git2::Time;
-use std::path::PathBuf;
+use git2::{Oid, Time};
+use std::path::{Path, PathBuf};
+use regex::Regex;
+use serde::Serialize;
 use url::Url;
 use atom_syndication::Link;
 use chrono::FixedOffset;
 use mdbook::book::Book;
+use mdbook::{Config, MDBook};
 use mdbook::errors::Error;
 use mdbook::preprocess::{Preprocessor, PreprocessorContext};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use atom_syndication::extension::{Extension, ExtensionMap};
 use std::fs;
-use crate::post_finder::{Author, Post, PostFinder};
+use std::io::{self, BufReader};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::time::Duration;
+use crate::errors::{AtomError, ConfigError, SearchError, WriteError};
+use crate::external_feeds::{load_external_feeds, merge_external_feeds};
+use crate::fingerprints::apply_fingerprints;
+use crate::library_helpers::{log_progress, parse_author_limit, parse_blame_config, parse_commit_range, parse_date_overrides, parse_entry_authors, parse_excluded_paths, parse_invalid_utf8, parse_reading_speed, parse_feed_image, parse_remote_fallback, parse_renderers, parse_retry_open_attempts, parse_section, parse_sort, parse_translations, write_feed_env, write_if_changed, FEED_ENV_FILE};
+use crate::output_locator::{Destination, OutputLocator};
+use crate::post_finder::{chapter_ids, DateOverride, sort_posts, Author, AuthorLimit, BlameConfig, EntryAuthors, HistoryProvider, InvalidUtf8, Post, PostFinder, SortDirection, SortField};
+use crate::post_set::most_recent;
+use crate::remote_history::{RemoteFallback, RemoteHistory};
 
-pub struct AtomProcessor;
-struct AtomGenerator;
+// Reads its options from the book's [preprocessor.git-atom] section and the history from the book's repository, unless
+// they're given to it, like when a custom mdbook driver or a test makes the preprocessor.
+#[derive(Default)]
+pub struct AtomProcessor {
+    config: Option<AtomConfig>,
+    history_provider: Option<Box<dyn HistoryProvider>>,
+}
 
-struct AtomConfig {
-    title: String,
-    base_url: Url,
-    content_path: PathBuf,
-    root_path: PathBuf,
+// Turns posts into a feed. Library users can make feeds from the posts PostFinder finds without running mdbook.
+pub struct AtomGenerator {
+    title_template: String,
+    entry_authors: EntryAuthors,
+    author_limit: AuthorLimit,
+    feed_contributors: bool,
+    thumbnails: bool,
+    language: Option<String>,
+    include_source: bool,
+    xml_base: bool,
+    entry_order: EntryOrder,
+    machine_metadata: bool,
+    // Where the feed is published, which identifies it instead of the book's url when the book has more than one
+    // feed, like one for each translation.
+    feed_url: Option<Url>,
+}
+
+const GIT_ATOM_PREFIX: &str = "mdbook-git-atom";
+const GIT_ATOM_NAMESPACE: &str = "https://github.com/younata/mdbook-git-atom";
+
+const MEDIA_PREFIX: &str = "media";
+const MEDIA_NAMESPACE: &str = "http://search.yahoo.com/mrss/";
+
+// Deleted entries, from RFC 6721.
+const TOMBSTONES_PREFIX: &str = "at";
+const TOMBSTONES_NAMESPACE: &str = "http://purl.org/atompub/tombstones/1.0";
+
+const TITLE_PLACEHOLDERS: [&str; 3] = ["{book}", "{section}", "{title}"];
+
+// The options of the atom feed, usually read from the book's config.
+pub struct AtomConfig {
+    pub(crate) title: String,
+    pub(crate) base_url: Url,
+    pub(crate) content_path: PathBuf,
+    // Finds the directories the feed and the other generated files are written to. The renderer writes every file to
+    // its own directory in the build directory.
+    pub(crate) output_locator: OutputLocator,
+    // Where atom.xml, posts.json and opensearch.xml are written, from feed_destination, manifest_destination and
+    // opensearch_destination. "src" or "build", which all default to "src".
+    pub(crate) feed_destination: Destination,
+    pub(crate) manifest_destination: Destination,
+    pub(crate) opensearch_destination: Destination,
+    pub(crate) root_path: PathBuf,
     // Max number of lines in the article to include. 0 means no preview, -1 means whole article. Defaults to 0.
-    maximum_number_of_lines: i64,
+    pub(crate) maximum_number_of_lines: i64,
     // Target number of entries in the atom feed to create. Defaults to 10.
-    // Set this to 0 to get the old behavior where minimum_number_of_commits is paid attention to.
-    // This basically overrides minimum_number_of_commits when it's a positive number.
-    // We'll search as far back as necessary to create the target amount of entries.
-    target_number_of_entries: i64,
+    // Set this to 0 to include every post.
+    pub(crate) target_number_of_entries: i64,
+    // Whether chapters without a source file (e.g. created by another preprocessor) are included, dated by HEAD.
+    // Defaults to true.
+    pub(crate) include_synthesized_chapters: bool,
+    // Chapters nested deeper than this many levels are rolled up into their ancestor at this depth,
+    // which becomes a single entry updated whenever any of its descendants are. 0 disables this. Defaults to 0.
+    pub(crate) rollup_depth: usize,
+    // Template for entry titles. {book} is the book title, {section} the part or top-level directory of
+    // the chapter and {title} the chapter title. Defaults to "{title}".
+    pub(crate) title_template: String,
+    // Which authors to credit on each entry: "all", "primary" (the author owning the most lines) or
+    // "top:N" (the N authors owning the most lines). Defaults to "all".
+    pub(crate) entry_authors: EntryAuthors,
+    // At most max_entry_authors of those are credited, followed by an overflow_author like "and 3 others" when
+    // there were more. Defaults to crediting all of them.
+    pub(crate) author_limit: AuthorLimit,
+    // Whether everyone who authored an entry in the feed is listed as a contributor to the feed. Defaults to true.
+    pub(crate) feed_contributors: bool,
+    // Reading speed used to estimate each entry's reading time, in words per minute. Defaults to 200.
+    pub(crate) words_per_minute: u32,
+    // Whether words in code blocks count towards word counts and reading times. Defaults to true.
+    pub(crate) count_code_blocks: bool,
+    // What posts are sorted by: "last_modified", "created" or "title". Defaults to "last_modified".
+    pub(crate) sort_field: SortField,
+    // "ascending" or "descending". Defaults to "descending".
+    pub(crate) sort_direction: SortDirection,
+    // The order of the entries in the feed, "newest_first" or "oldest_first" by their updated date, whatever posts were
+    // sorted by to pick them. Defaults to "newest_first".
+    pub(crate) entry_order: EntryOrder,
+    // Whether each entry has mdbook-git-atom:sourcePath, mdbook-git-atom:lastCommit, mdbook-git-atom:createdCommit
+    // and mdbook-git-atom:revisionCount elements, so tools can map entries back to the repository. Defaults to false.
+    pub(crate) machine_metadata: bool,
+    // Whether the first image of each chapter is advertised as the entry's thumbnail. Defaults to true.
+    pub(crate) thumbnails: bool,
+    // Whether to write posts.json, describing the posts for client-side use, next to the feed. Defaults to false.
+    pub(crate) manifest: bool,
+    // Whether an opensearch.xml is written next to the feed, so browsers can search the book. Defaults to false.
+    pub(crate) generate_opensearch: bool,
+    // Whether entries whose content only changed in formatting keep their previous updated date. Defaults to false.
+    pub(crate) content_fingerprint: bool,
+    // Maximum number of posts in posts.json. 0, the default, includes every post.
+    pub(crate) manifest_entries: i64,
+    // Whether entries that were in the previously generated feed, but whose chapters are gone, are marked as deleted.
+    // Defaults to false.
+    pub(crate) tombstones: bool,
+    // How many days a deleted entry is kept in the feed for. 0 keeps them forever. Defaults to 30.
+    pub(crate) tombstone_max_age_days: i64,
+    // Language of the feed and its entries, like "de". Defaults to the book's language, if it has one.
+    pub(crate) language: Option<String>,
+    // Languages whose chapters are in a top-level directory of the same name. Each gets its own feed in that
+    // directory, in that language. Defaults to none, which writes a single feed for the whole book.
+    pub(crate) translations: Vec<String>,
+    // Whether a "# " heading of the title that a chapter starts with is left out of its content, since it repeats the
+    // entry's title. Defaults to true.
+    pub(crate) strip_h1_from_content: bool,
+    // Lines of entry content longer than this many bytes, like embedded images, are cut short. 0 keeps lines of any
+    // length. Defaults to 10000.
+    pub(crate) max_preview_line_bytes: usize,
+    // Other feeds whose entries are merged into the generated one, as urls or paths relative to the book root.
+    // Defaults to none.
+    pub(crate) merge_feeds: Vec<String>,
+    // Whether merge_feeds skips feeds that would have to be fetched over the network. Defaults to false.
+    pub(crate) offline: bool,
+    // Other books in the same repository, relative to the book root, whose chapters are also in the feed.
+    // Each is expected to be published at its directory name under base_url. Defaults to none.
+    pub(crate) extra_books: Vec<PathBuf>,
+    // Set with first_parent and blame_track_copies, which both default to true, max_blame_depth, which defaults
+    // to 10000, blame_timeout_seconds, which defaults to 30, max_blame_hunks, which defaults to 10000, blame_since and
+    // blame_until, which don't limit blame to any dates by default, max_blame_file_bytes, which defaults to 1000000,
+    // merge_commit_dates, "use" or "skip", which defaults to "use", and follow_renames, which defaults to false.
+    pub(crate) blame_config: BlameConfig,
+    // What to do with chapters that aren't valid UTF-8: "lossy", "skip" or "error". Defaults to "lossy".
+    pub(crate) invalid_utf8: InvalidUtf8,
+    // Chapters that never become entries, relative to the src directory. These are the paths in exclude, along with
+    // SUMMARY.md unless exclude_defaults is false.
+    pub(crate) excluded_paths: Vec<PathBuf>,
+    // Whether each entry has a source element describing the feed it's from. Defaults to false.
+    pub(crate) include_source: bool,
+    // Whether the feed and each entry's content set xml:base, so readers resolve relative links in content.
+    // Defaults to false.
+    pub(crate) xml_base: bool,
+    // How many times opening the repository is tried, waiting longer after each failure. Defaults to 1.
+    pub(crate) retry_open_attempts: u32,
+    // Only chapters in this part of the book, or top-level directory when it has no parts, are in the feed.
+    // Defaults to every chapter.
+    pub(crate) section: Option<String>,
+    // Whether each entry's content ends with the headings of the sections its latest commit changed.
+    // Defaults to false.
+    pub(crate) changed_sections: bool,
+    // Where to look up the history of chapters older than a shallow clone.
+    pub(crate) remote_fallback: Option<RemoteFallback>,
+    // The notes ref with dates that override when chapters were created, like "refs/notes/publish-dates".
+    pub(crate) notes_ref: Option<String>,
+    // The license of entries whose chapters don't set rights in their front matter, like "CC-BY-4.0".
+    pub(crate) entry_rights: Option<String>,
+    // Dates that override the ones from git, read from dates_file. Defaults to those in dates.toml, if it exists.
+    pub(crate) date_overrides: HashMap<PathBuf, DateOverride>,
+    // Chapters that haven't been modified in this many days are left out of the feed. Defaults to 0, which keeps
+    // every chapter.
+    pub(crate) max_entry_age_days: u64,
+    // The book's [output.html.redirect] table, from pages like "/old.html" to where they moved.
+    pub(crate) redirects: BTreeMap<String, String>,
+    // Whether the build fails when the feed can't be written. Defaults to failing.
+    pub(crate) on_write_error: OnWriteError,
+    // Whether a feed without entries is written when there are no chapters to put in it, rather than failing the
+    // build. Defaults to false.
+    pub(crate) allow_empty_feed: bool,
+    // Only chapters changed after the first commit and up to the second are in the feed, like the changes of a release.
+    // Set with the MDBOOK_GIT_ATOM_FROM_COMMIT and MDBOOK_GIT_ATOM_TO_COMMIT environment variables. Defaults to none.
+    pub(crate) commit_range: Option<(Oid, Oid)>,
+    // Where files that can't be written to the src directory go instead, which is the build directory when
+    // fallback_output is true. Defaults to none.
+    pub(crate) fallback_output_path: Option<PathBuf>,
+    // The renderers the feed is written for. Defaults to just html.
+    pub(crate) renderers: Vec<String>,
+    // Urls of the feed's icon and logo, from either urls or paths in the src directory.
+    pub(crate) icon: Option<String>,
+    pub(crate) logo: Option<String>,
+    // Whether the book is being built by mdbook serve, which rebuilds it whenever anything in the src directory
+    // changes. Defaults to false.
+    pub(crate) serving: bool,
+}
+
+// The order of the entries in the feed's document, by their updated date.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryOrder {
+    NewestFirst,
+    OldestFirst,
+}
+
+impl FromStr for EntryOrder {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "newest_first" => Ok(EntryOrder::NewestFirst),
+            "oldest_first" => Ok(EntryOrder::OldestFirst),
+            _ => Err(format!("{}. Expected \"newest_first\" or \"oldest_first\".", value)),
+        }
+    }
+}
+
+// What happens when the feed can't be written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnWriteError {
+    Fail,
+    // Logs a warning and lets the book build without the feed.
+    Warn,
+}
+
+impl FromStr for OnWriteError {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "fail" => Ok(OnWriteError::Fail),
+            "warn" => Ok(OnWriteError::Warn),
+            _ => Err(format!("{}. Expected \"fail\" or \"warn\".", value)),
+        }
+    }
+}
+
+// What happens when the icon or logo is a path to a file that doesn't exist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnMissingAsset {
+    Fail,
+    // Logs a warning and links to the file anyway.
+    Warn,
+}
+
+impl FromStr for OnMissingAsset {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "fail" => Ok(OnMissingAsset::Fail),
+            "warn" => Ok(OnMissingAsset::Warn),
+            _ => Err(format!("{}. Expected \"fail\" or \"warn\".", value)),
+        }
+    }
 }
 
 impl AtomConfig {
-    fn from_book_config(ctx: &PreprocessorContext, name: &str) -> Option<AtomConfig> {
-        let section_config = ctx.config.get_preprocessor(name)?;
+    pub fn from_book_config(ctx: &PreprocessorContext, name: &str) -> Result<AtomConfig, ConfigError> {
+        let section_config = ctx.config.get_preprocessor(name)
+            .ok_or_else(|| ConfigError::MissingSection(name.to_string()))?;
+        let mut config = AtomConfig::from_section(&ctx.root, &ctx.config, section_config)?;
+        // mdbook serve sets the html renderer's live reload endpoint before building, which is the only sign of it
+        // preprocessors get.
+        config.serving = ctx.config.get("output.html.live-reload-endpoint").is_some();
+        Ok(config)
+    }
+
+    // Reads the options in section_config, which is either the preprocessor's section or the renderer's.
+    pub fn from_section(root: &Path, book_config: &Config, section_config: &toml::value::Table) -> Result<AtomConfig, ConfigError> {
 
         let base_url_str: &str;
         if let Some(toml::value::Value::String(base_url)) = section_config.get("base_url") {
             base_url_str = base_url.as_str();
         } else {
-            return None
+            return Err(ConfigError::MissingBaseUrl)
+        }
+        let base_url = Url::parse(base_url_str).map_err(|_| ConfigError::InvalidBaseUrl(base_url_str.to_string()))?;
+        // Anything else, like file:// urls, is useless in a public feed.
+        if base_url.scheme() != "https" && base_url.scheme() != "http" {
+            return Err(ConfigError::BaseUrlMustBeHttp(base_url_str.to_string()));
         }
         let mut article_lines: &i64 = &0;
-        if let Some(toml::Value::Integer(max_lines)) = section_config.get("article_preview_lines") {
+        // preview_lines replaces article_preview_lines, which is still read from older configs.
+        let preview_lines_key = match (section_config.get("preview_lines"), section_config.get("article_preview_lines")) {
+            (Some(_), Some(_)) => {
+                log::warn!("Both preview_lines and article_preview_lines are set, using preview_lines");
+                "preview_lines"
+            }
+            (None, Some(_)) => "article_preview_lines",
+            _ => "preview_lines",
+        };
+        if let Some(toml::Value::Integer(max_lines)) = section_config.get(preview_lines_key) {
             if (*max_lines) < -1 {
-                panic!("Invalid number of article preview lines specified: {}. Expected 0 or a positive number.", max_lines);
+                return Err(ConfigError::invalid_value(preview_lines_key, format!("{}. Expected -1, 0 or a positive number.", max_lines)));
             }
             article_lines = max_lines;
         }
         let mut target_number_of_entries: &i64 = &10;
         if let Some(toml::Value::Integer(target_entries)) = section_config.get("target_number_of_entries") {
             if (*target_entries) < -1 {
-                panic!("Invalid target number of entries provided: {}. Expected 0 or a positive number.", target_entries);
+                return Err(ConfigError::invalid_value("target_number_of_entries", format!("{}. Expected 0 or a positive number.", target_entries)));
             }
             target_number_of_entries = target_entries;
         }
+        let mut include_synthesized_chapters = true;
+        if let Some(toml::Value::Boolean(include_synthesized)) = section_config.get("include_synthesized_chapters") {
+            include_synthesized_chapters = *include_synthesized;
+        }
+        let mut rollup_depth = 0;
+        if let Some(toml::Value::Integer(depth)) = section_config.get("rollup_depth") {
+            if (*depth) < 0 {
+                return Err(ConfigError::invalid_value("rollup_depth", format!("{}. Expected 0 or a positive number.", depth)));
+            }
+            rollup_depth = *depth as usize;
+        }
+        let mut title_template = "{title}";
+        if let Some(toml::Value::String(template)) = section_config.get("title_template") {
+            let placeholder_regex = Regex::new(r"\{[^{}]*\}").unwrap();
+            for placeholder in placeholder_regex.find_iter(template) {
+                if !TITLE_PLACEHOLDERS.contains(&placeholder.as_str()) {
+                    return Err(ConfigError::invalid_value("title_template", format!("unknown placeholder {}. Expected one of {}.", placeholder.as_str(), TITLE_PLACEHOLDERS.join(", "))));
+                }
+            }
+            title_template = template.as_str();
+        }
+        let entry_authors = parse_entry_authors(section_config)?;
+        let author_limit = parse_author_limit(section_config)?;
+        let (words_per_minute, count_code_blocks) = parse_reading_speed(section_config)?;
+        let (sort_field, sort_direction) = parse_sort(section_config)?;
+        let mut entry_order = EntryOrder::NewestFirst;
+        if let Some(toml::Value::String(configured_entry_order)) = section_config.get("entry_order") {
+            entry_order = configured_entry_order.parse()
+                .map_err(|message| ConfigError::invalid_value("entry_order", message))?;
+        }
+        let mut machine_metadata = false;
+        if let Some(toml::Value::Boolean(configured_machine_metadata)) = section_config.get("machine_metadata") {
+            machine_metadata = *configured_machine_metadata;
+        }
+        let mut manifest = false;
+        if let Some(toml::Value::Boolean(configured_manifest)) = section_config.get("manifest") {
+            manifest = *configured_manifest;
+        }
+        let mut content_fingerprint = false;
+        if let Some(toml::Value::Boolean(configured_content_fingerprint)) = section_config.get("content_fingerprint") {
+            content_fingerprint = *configured_content_fingerprint;
+        }
+        let mut generate_opensearch = false;
+        if let Some(toml::Value::Boolean(configured_generate_opensearch)) = section_config.get("generate_opensearch") {
+            generate_opensearch = *configured_generate_opensearch;
+        }
+        let mut manifest_entries = 0;
+        if let Some(toml::Value::Integer(configured_manifest_entries)) = section_config.get("manifest_entries") {
+            if *configured_manifest_entries < 0 {
+                return Err(ConfigError::invalid_value("manifest_entries", format!("{}. Expected 0 or a positive number.", configured_manifest_entries)));
+            }
+            manifest_entries = *configured_manifest_entries;
+        }
+        let mut tombstones = false;
+        if let Some(toml::Value::Boolean(configured_tombstones)) = section_config.get("tombstones") {
+            tombstones = *configured_tombstones;
+        }
+        let mut tombstone_max_age_days = 30;
+        if let Some(toml::Value::Integer(max_age)) = section_config.get("tombstone_max_age_days") {
+            if *max_age < 0 {
+                return Err(ConfigError::invalid_value("tombstone_max_age_days", format!("{}. Expected 0 or a positive number.", max_age)));
+            }
+            tombstone_max_age_days = *max_age;
+        }
+        let mut language = book_config.book.language.clone();
+        if let Some(toml::Value::String(configured_language)) = section_config.get("language") {
+            language = Some(configured_language.to_string());
+        }
+        let translations = parse_translations(section_config)?;
+        let mut merge_feeds: Vec<String> = vec![];
+        if let Some(configured_merge_feeds) = section_config.get("merge_feeds") {
+            merge_feeds = configured_merge_feeds.as_array()
+                .and_then(|sources| sources.iter().map(|source| source.as_str().map(|source| source.to_string())).collect())
+                .ok_or_else(|| ConfigError::invalid_value("merge_feeds", format!("{}. Expected a list of urls or paths.", configured_merge_feeds)))?;
+        }
+        let mut extra_books: Vec<PathBuf> = vec![];
+        if let Some(configured_extra_books) = section_config.get("extra_books") {
+            extra_books = configured_extra_books.as_array()
+                .and_then(|books| books.iter().map(|book| book.as_str().map(PathBuf::from)).collect())
+                .ok_or_else(|| ConfigError::invalid_value("extra_books", format!("{}. Expected a list of paths.", configured_extra_books)))?;
+        }
+        let invalid_utf8 = parse_invalid_utf8(section_config)?;
+        let excluded_paths = parse_excluded_paths(section_config)?;
+        let retry_open_attempts = parse_retry_open_attempts(section_config)?;
+        let section = parse_section(section_config);
+        let remote_fallback = parse_remote_fallback(section_config)?;
+        let date_overrides = parse_date_overrides(section_config, root)?;
+        let notes_ref = match section_config.get("notes_ref") {
+            Some(toml::Value::String(notes_ref)) => Some(notes_ref.to_string()),
+            _ => None,
+        };
+        let entry_rights = match section_config.get("entry_rights") {
+            Some(toml::Value::String(entry_rights)) => Some(entry_rights.to_string()),
+            _ => None,
+        };
+        let redirects: BTreeMap<String, String> = match book_config.get("output.html.redirect") {
+            Some(toml::Value::Table(redirects)) => redirects.iter()
+                .filter_map(|(source, target)| Some((source.to_string(), target.as_str()?.to_string())))
+                .collect(),
+            _ => BTreeMap::new(),
+        };
+        let mut changed_sections = false;
+        if let Some(toml::Value::Boolean(configured_changed_sections)) = section_config.get("changed_sections") {
+            changed_sections = *configured_changed_sections;
+        }
+        let mut xml_base = false;
+        if let Some(toml::Value::Boolean(configured_xml_base)) = section_config.get("xml_base") {
+            xml_base = *configured_xml_base;
+        }
+        let mut include_source = false;
+        if let Some(toml::Value::Boolean(configured_include_source)) = section_config.get("include_source") {
+            include_source = *configured_include_source;
+        }
+        let blame_config = parse_blame_config(section_config)?;
+        let max_preview_line_bytes = match section_config.get("max_preview_line_bytes") {
+            None => 10_000,
+            Some(toml::Value::Integer(bytes)) => usize::try_from(*bytes)
+                .map_err(|_| ConfigError::invalid_value("max_preview_line_bytes", format!("{}. Expected 0 or a positive number.", bytes)))?,
+            Some(value) => return Err(ConfigError::invalid_value("max_preview_line_bytes", format!("{}. Expected a number of bytes.", value))),
+        };
+        let mut offline = false;
+        if let Some(toml::Value::Boolean(configured_offline)) = section_config.get("offline") {
+            offline = *configured_offline;
+        }
+        let mut strip_h1_from_content = true;
+        if let Some(toml::Value::Boolean(strip_h1)) = section_config.get("strip_h1_from_content") {
+            strip_h1_from_content = *strip_h1;
+        }
+        let mut thumbnails = true;
+        if let Some(toml::Value::Boolean(configured_thumbnails)) = section_config.get("thumbnails") {
+            thumbnails = *configured_thumbnails;
+        }
+        let mut allow_empty_feed = false;
+        if let Some(toml::Value::Boolean(configured_allow_empty_feed)) = section_config.get("allow_empty_feed") {
+            allow_empty_feed = *configured_allow_empty_feed;
+        }
+        let mut on_write_error = OnWriteError::Fail;
+        if let Some(toml::Value::String(configured_on_write_error)) = section_config.get("on_write_error") {
+            on_write_error = configured_on_write_error.parse()
+                .map_err(|message| ConfigError::invalid_value("on_write_error", message))?;
+        }
+        let max_entry_age_days = match section_config.get("max_entry_age_days") {
+            None => 0,
+            Some(toml::Value::Integer(days)) => u64::try_from(*days)
+                .map_err(|_| ConfigError::invalid_value("max_entry_age_days", format!("{}. Expected 0 or a positive number.", days)))?,
+            Some(value) => return Err(ConfigError::invalid_value("max_entry_age_days", format!("{}. Expected a number of days.", value))),
+        };
+        let renderers = parse_renderers(section_config)?;
+        let output_locator = OutputLocator::new(root, book_config);
+        let fallback_output_path = match section_config.get("fallback_output") {
+            Some(toml::Value::Boolean(true)) => Some(output_locator.build_dir().to_path_buf()),
+            _ => None,
+        };
+        let parse_destination = |key: &str| -> Result<Destination, ConfigError> {
+            match section_config.get(key) {
+                None => Ok(Destination::Src),
+                Some(toml::Value::String(destination)) => destination.parse()
+                    .map_err(|message| ConfigError::invalid_value(key, message)),
+                Some(value) => Err(ConfigError::invalid_value(key, format!("{}. Expected \"src\" or \"build\".", value))),
+            }
+        };
+        let feed_destination = parse_destination("feed_destination")?;
+        let manifest_destination = parse_destination("manifest_destination")?;
+        let opensearch_destination = parse_destination("opensearch_destination")?;
+        let mut feed_contributors = true;
+        if let Some(toml::Value::Boolean(contributors)) = section_config.get("feed_contributors") {
+            feed_contributors = *contributors;
+        }
+        let base_url = normalized_base_url(base_url);
+        let mut on_missing_asset = OnMissingAsset::Fail;
+        if let Some(toml::Value::String(configured_on_missing_asset)) = section_config.get("on_missing_asset") {
+            on_missing_asset = configured_on_missing_asset.parse()
+                .map_err(|message| ConfigError::invalid_value("on_missing_asset", message))?;
+        }
+        let src_path = root.join(&book_config.book.src);
+        let icon = parse_feed_image(section_config, "icon", &src_path, &base_url, on_missing_asset)?;
+        let logo = parse_feed_image(section_config, "logo", &src_path, &base_url, on_missing_asset)?;
 
-        Some(AtomConfig {
-            title: ctx.config.book.title.as_ref()?.to_string(),
-            base_url: Url::parse(base_url_str).ok()?,
-            content_path: ctx.config.book.src.to_path_buf(),
-            root_path: ctx.root.to_path_buf(),
+        Ok(AtomConfig {
+            title: book_config.book.title.as_ref().ok_or(ConfigError::MissingTitle)?.to_string(),
+            base_url,
+            content_path: book_config.book.src.to_path_buf(),
+            output_locator,
+            feed_destination,
+            manifest_destination,
+            opensearch_destination,
+            root_path: root.to_path_buf(),
             maximum_number_of_lines: *article_lines,
             target_number_of_entries: *target_number_of_entries,
+            include_synthesized_chapters,
+            rollup_depth,
+            title_template: title_template.to_string(),
+            entry_authors,
+            author_limit,
+            feed_contributors,
+            words_per_minute,
+            count_code_blocks,
+            sort_field,
+            sort_direction,
+            entry_order,
+            machine_metadata,
+            thumbnails,
+            manifest,
+            generate_opensearch,
+            content_fingerprint,
+            manifest_entries,
+            tombstones,
+            tombstone_max_age_days,
+            language,
+            translations,
+            strip_h1_from_content,
+            max_preview_line_bytes,
+            merge_feeds,
+            offline,
+            extra_books,
+            blame_config,
+            invalid_utf8,
+            excluded_paths,
+            include_source,
+            xml_base,
+            retry_open_attempts,
+            section,
+            changed_sections,
+            remote_fallback,
+            notes_ref,
+            entry_rights,
+            date_overrides,
+            max_entry_age_days,
+            redirects,
+            on_write_error,
+            allow_empty_feed,
+            commit_range: parse_commit_range()?,
+            fallback_output_path,
+            renderers,
+            icon,
+            logo,
+            serving: false,
         })
     }
 }
 
+impl AtomConfig {
+    // Where the feed is published. Translated books have a feed in each translation's directory instead.
+    pub(crate) fn feed_url(&self, translation: Option<&str>) -> Url {
+        let path = match translation {
+            Some(translation) => format!("{}/atom.xml", translation),
+            None => "atom.xml".to_string(),
+        };
+        self.base_url.join(&path).unwrap_or_else(|_| self.base_url.clone())
+    }
+
+    pub(crate) fn post_finder(&self) -> PostFinder {
+        PostFinder::new(&self.root_path)
+            .with_retry_open_attempts(self.retry_open_attempts)
+            .with_synthesized_chapters(self.include_synthesized_chapters)
+            .with_rollup_depth(self.rollup_depth)
+            .with_reading_speed(self.words_per_minute, self.count_code_blocks)
+            .with_strip_h1_from_content(self.strip_h1_from_content)
+            .with_max_preview_line_bytes(self.max_preview_line_bytes)
+            .with_blame_commit_range(self.commit_range)
+            .with_blame_config(self.blame_config)
+            .with_invalid_utf8(self.invalid_utf8)
+            .with_excluded_paths(self.excluded_paths.clone())
+            .with_changed_sections(self.changed_sections)
+            .with_remote_history(self.remote_fallback.clone().map(RemoteHistory::new))
+            .with_notes_ref(self.notes_ref.clone())
+            .with_entry_rights(self.entry_rights.clone())
+            .with_date_overrides(self.date_overrides.clone())
+            .with_max_age(Some(self.max_entry_age_days).filter(|days| *days > 0).map(|days| Duration::from_secs(days.saturating_mul(86_400))))
+    }
+
+    // Every post in the book and any extra books, in the configured order.
+    pub(crate) fn search(&self, history: &dyn HistoryProvider, book: &Book) -> Result<Vec<Post>, Error> {
+        let main_posts = history.search_with_progress(book, &self.content_path, Some(self.maximum_number_of_lines), 0, (self.sort_field, self.sort_direction), &log_progress);
+        if self.extra_books.is_empty() {
+            return Ok(main_posts?);
+        }
+
+        // The extra books can have the posts the main book doesn't.
+        let mut all_posts = match main_posts {
+            Err(SearchError::EmptyResult) => vec![],
+            main_posts => main_posts?,
+        };
+        for extra_book_path in self.extra_books.iter() {
+            let extra_book = MDBook::load(self.root_path.join(extra_book_path))?;
+            // Chapters are blamed relative to the repository, which is the main book's root.
+            let content_path = extra_book_path.join(&extra_book.config.book.src);
+            let slug = extra_book_path.file_name().map(PathBuf::from).unwrap_or_default();
+            for mut post in history.find_posts(&extra_book.book, &content_path, Some(self.maximum_number_of_lines), (self.sort_field, self.sort_direction)) {
+                // Prefixing with the book's directory keeps ids unique across books and links the entry under it.
+                post.path = slug.join(&post.path);
+                post.id = post.path.to_str().unwrap_or("").to_string();
+                all_posts.push(post);
+            }
+        }
+        if all_posts.is_empty() {
+            return Err(SearchError::EmptyResult.into());
+        }
+        sort_posts(&mut all_posts, self.sort_field, self.sort_direction);
+        Ok(all_posts)
+    }
+
+    // The ids of the posts for every chapter in the book and any extra books, whether or not search found them.
+    fn chapter_ids(&self, book: &Book) -> Result<HashSet<String>, Error> {
+        let mut ids = chapter_ids(book, &self.content_path);
+        for extra_book_path in self.extra_books.iter() {
+            let extra_book = MDBook::load(self.root_path.join(extra_book_path))?;
+            let content_path = extra_book_path.join(&extra_book.config.book.src);
+            let slug = extra_book_path.file_name().map(PathBuf::from).unwrap_or_default();
+            ids.extend(chapter_ids(&extra_book.book, &content_path).into_iter()
+                .filter_map(|id| Some(slug.join(id).to_str()?.to_string())));
+        }
+        Ok(ids)
+    }
+}
+
+// Url::join replaces the last path segment of a base url without a trailing slash, so make sure there is one.
+fn normalized_base_url(mut base_url: Url) -> Url {
+    if !base_url.path().ends_with('/') {
+        let path = format!("{}/", base_url.path());
+        log::warn!("base_url \"{}\" doesn't end with a slash, appending one", base_url);
+        base_url.set_path(&path);
+    }
+    base_url
+}
+
 impl Preprocessor for AtomProcessor {
     fn name(&self) -> &str {
         "git-atom"
     }
 
     fn run(&self, ctx: &PreprocessorContext, book: Book) -> Result<Book, Error> {
-        let config = AtomConfig::from_book_config(&ctx, self.name()).expect("Create atom configuration");
-
-        let post_finder = PostFinder::new(config.root_path.to_str().expect("Create PostFinder"));
-        let posts = post_finder.search(&book, &config.content_path, Some(config.maximum_number_of_lines), config.target_number_of_entries);
+        let book_config;
+        let config = match self.config.as_ref() {
+            Some(config) => config,
+            None => {
+                book_config = AtomConfig::from_book_config(ctx, self.name())?;
+                &book_config
+            }
+        };
+        if !config.renderers.contains(&ctx.renderer) {
+            log::debug!("Not writing the atom feed for the {} renderer", ctx.renderer);
+            return Ok(book);
+        }
 
-        let generator = AtomGenerator {};
-        let feed = generator.generate(posts, config.title, config.base_url);
+        let post_finder;
+        let history: &dyn HistoryProvider = match self.history_provider.as_deref() {
+            Some(history_provider) => history_provider,
+            None => {
+                post_finder = config.post_finder();
+                &post_finder
+            }
+        };
+        history.open()?;
+        if !history.has_history() {
+            log::warn!("No git history found, skipping atom feed generation");
+            return Ok(book);
+        }
+        // The manifest can include more posts than the feed, so the feed's entries are picked from all of them.
+        let all_posts = config.search(history, &book)?;
 
-        let feed_path: PathBuf = config.content_path.join("atom.xml");
-        fs::write(feed_path, feed.to_string()).expect("Write atom.xml");
+        AtomProcessor::write_feed(config, &book, all_posts, history.head_time())?;
+        if let Err(error) = write_feed_env(&config.root_path, &config.feed_url(None)) {
+            log::warn!("Couldn't write the feed's url to {}: {}", FEED_ENV_FILE, error);
+        }
 
         Ok(book)
     }
 
-    fn supports_renderer(&self, renderer: &str) -> bool {
-        renderer == "html"
+    // Which renderers the feed is written for is configured with renderers, which needs the context, so run checks it.
+    fn supports_renderer(&self, _renderer: &str) -> bool {
+        true
+    }
+}
+
+impl AtomProcessor {
+    pub fn new() -> AtomProcessor {
+        AtomProcessor::default()
+    }
+
+    // Used instead of the book's [preprocessor.git-atom] section.
+    pub fn with_config(mut self, config: AtomConfig) -> AtomProcessor {
+        self.config = Some(config);
+        self
+    }
+
+    // Used instead of searching the book's repository for posts.
+    pub fn with_post_finder(mut self, history_provider: Box<dyn HistoryProvider>) -> AtomProcessor {
+        self.history_provider = Some(history_provider);
+        self
+    }
+
+    // Writes atom.xml, and posts.json when it's enabled, given every post in book in the configured order.
+    // Chapters deleted since the last build are marked as deleted at head_time.
+    // Writes the feeds, unless on_write_error says to carry on without them when they can't be written.
+    pub(crate) fn write_feed(config: &AtomConfig, book: &Book, all_posts: Vec<Post>, head_time: Option<Time>) -> Result<(), Error> {
+        // Chapters searches leave out, like ones older than max_entry_age_days, still exist, so they aren't deleted.
+        let head_time = head_time.filter(|_| config.tombstones);
+        let chapter_ids = match head_time {
+            Some(_) => config.chapter_ids(book)?,
+            None => HashSet::new(),
+        };
+        match AtomProcessor::write_feeds(config, all_posts, &chapter_ids, head_time) {
+            Err(error) if config.on_write_error == OnWriteError::Warn && error.is::<WriteError>() => {
+                log::warn!("{}, continuing without the atom feed", error);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn write_feeds(config: &AtomConfig, mut all_posts: Vec<Post>, chapter_ids: &HashSet<String>, head_time: Option<Time>) -> Result<(), Error> {
+        if config.content_fingerprint {
+            apply_fingerprints(&config.root_path, &config.content_path, &mut all_posts);
+            sort_posts(&mut all_posts, config.sort_field, config.sort_direction);
+        }
+        if config.generate_opensearch {
+            write_output(config, &config.output_locator.locate(config.opensearch_destination, "opensearch.xml"), &opensearch_description(&config.title, &config.base_url))?;
+        }
+        if let Some(section) = config.section.as_ref() {
+            all_posts.retain(|post| post.section.as_ref() == Some(section));
+            if all_posts.is_empty() {
+                log::warn!("No chapters found in section {}, skipping atom feed generation", section);
+                return Ok(());
+            }
+        }
+        let external_feeds = load_external_feeds(&config.merge_feeds, &config.root_path, config.offline);
+        if config.translations.is_empty() {
+            return AtomProcessor::write_feed_to(config, Path::new(""), config.language.as_deref(), all_posts, chapter_ids, head_time, &external_feeds);
+        }
+
+        let mut remaining_posts = all_posts;
+        for language in config.translations.iter() {
+            let (posts, other_posts): (Vec<Post>, Vec<Post>) = remaining_posts.into_iter()
+                .partition(|post| post.path.starts_with(language));
+            remaining_posts = other_posts;
+            if posts.is_empty() {
+                log::warn!("No chapters found in {}, skipping its atom feed", language);
+                continue;
+            }
+            AtomProcessor::write_feed_to(config, Path::new(language), Some(language), posts, chapter_ids, head_time, &external_feeds)?;
+        }
+        Ok(())
+    }
+
+    // Writes the feed files for posts in a single language to directory, relative to their destinations. Entries of
+    // the previous feed that aren't chapters any more are tombstoned at head_time, when there is one.
+    fn write_feed_to(config: &AtomConfig, directory: &Path, language: Option<&str>, all_posts: Vec<Post>, chapter_ids: &HashSet<String>, head_time: Option<Time>, external_feeds: &[atom_syndication::Feed]) -> Result<(), Error> {
+        let generator = AtomGenerator {
+            title_template: config.title_template.to_string(),
+            entry_authors: config.entry_authors,
+            author_limit: config.author_limit.clone(),
+            feed_contributors: config.feed_contributors,
+            thumbnails: config.thumbnails,
+            language: language.map(|language| language.to_string()),
+            include_source: config.include_source,
+            xml_base: config.xml_base,
+            entry_order: config.entry_order,
+            machine_metadata: config.machine_metadata,
+            feed_url: Some(directory).filter(|directory| !directory.as_os_str().is_empty())
+                .map(|directory| config.feed_url(directory.to_str())),
+        };
+
+        if config.manifest {
+            let manifest_posts = most_recent(&all_posts, config.manifest_entries);
+            let mut manifest = generator.manifest(manifest_posts, &config.title, &config.base_url);
+            for entry in manifest.iter_mut() {
+                entry.url = redirected_url(&entry.url, &config.redirects, &config.base_url);
+            }
+            let manifest_path: PathBuf = config.output_locator.locate(config.manifest_destination, directory.join("posts.json"));
+            write_output(config, &manifest_path, &serde_json::to_string_pretty(&manifest).expect("Serialize posts.json"))?;
+        }
+
+        let feed_path: PathBuf = config.output_locator.locate(config.feed_destination, directory.join("atom.xml"));
+        // Posts that only fell out of the feed's window, or that searches left out, still exist, so they're compared
+        // against every chapter and post, along with every entry of the merged feeds, which the previous feed also has.
+        let tombstones = match head_time {
+            Some(head_time) => {
+                let current_ids: HashSet<&str> = all_posts.iter().map(|post| post.id.as_str())
+                    .chain(chapter_ids.iter().map(String::as_str))
+                    .chain(external_feeds.iter().flat_map(|external_feed| external_feed.entries.iter().map(|entry| entry.id.as_str())))
+                    .collect();
+                deleted_entries(&feed_path, &current_ids, head_time, config.tombstone_max_age_days)
+            }
+            None => vec![],
+        };
+
+        let posts: Vec<Post> = if config.target_number_of_entries > 0 {
+            all_posts.into_iter().take(config.target_number_of_entries as usize).collect()
+        } else {
+            all_posts
+        };
+        let mut feed = match generator.generate(posts, config.title.to_string(), config.base_url.clone()) {
+            Ok(feed) => feed,
+            Err(AtomError::NoPosts) if config.allow_empty_feed => {
+                log::warn!("There are no chapters to put in the atom feed, writing an empty feed");
+                generator.empty_feed(config.title.to_string(), config.base_url.clone())
+            }
+            Err(error) => return Err(error.into()),
+        };
+        feed.icon = config.icon.clone();
+        feed.logo = config.logo.clone();
+        // Entries keep their ids, so moving a page doesn't make it new to subscribers.
+        for link in feed.entries.iter_mut().flat_map(|entry| entry.links.iter_mut()).filter(|link| link.rel == "self") {
+            link.href = redirected_url(&link.href, &config.redirects, &config.base_url);
+        }
+        merge_external_feeds(&mut feed, external_feeds, config.target_number_of_entries);
+        if !tombstones.is_empty() {
+            feed.namespaces.insert(TOMBSTONES_PREFIX.to_string(), TOMBSTONES_NAMESPACE.to_string());
+            feed.extensions.insert(TOMBSTONES_PREFIX.to_string(), BTreeMap::from([("deleted-entry".to_string(), tombstones)]));
+        }
+
+        let mut feed_attributes = vec![];
+        if let Some(language) = language {
+            feed_attributes.push(("xml:lang", language));
+        }
+        if config.xml_base {
+            feed_attributes.push(("xml:base", config.base_url.as_str()));
+        }
+        Ok(write_output(config, &feed_path, &with_feed_attributes(feed.to_string(), &feed_attributes))?)
+    }
+}
+
+// atom_syndication's Feed has no fields for the xml:lang and xml:base attributes of the feed element itself, unlike
+// its Text and Content, so they're added to the serialized feed. Nothing else edits the serialized feed.
+fn with_feed_attributes(feed_xml: String, attributes: &[(&str, &str)]) -> String {
+    if attributes.is_empty() {
+        return feed_xml;
+    }
+    let attributes: String = attributes.iter()
+        .map(|(name, value)| format!("{}=\"{}\" ", name, html_escape::encode_double_quoted_attribute(value)))
+        .collect();
+    // The feed element is the first one after the xml declaration.
+    feed_xml.replacen("<feed ", &format!("<feed {}", attributes), 1)
+}
+
+// An OpenSearch description of mdbook's built-in search, whose search.html page searches for its query parameter.
+fn opensearch_description(title: &str, base_url: &Url) -> String {
+    // ShortName can't be longer than 16 characters.
+    let short_name: String = title.chars().take(16).collect();
+    let search_url = format!("{}?query={{searchTerms}}", base_url.join("search.html").unwrap_or_else(|_| base_url.clone()));
+    format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<OpenSearchDescription xmlns=\"http://a9.com/-/spec/opensearch/1.1/\">\n",
+            "  <ShortName>{}</ShortName>\n",
+            "  <Description>Search {}</Description>\n",
+            "  <InputEncoding>UTF-8</InputEncoding>\n",
+            "  <Url type=\"text/html\" template=\"{}\"/>\n",
+            "</OpenSearchDescription>\n",
+        ),
+        html_escape::encode_text(short_name.trim()),
+        html_escape::encode_text(title),
+        html_escape::encode_double_quoted_attribute(&search_url),
+    )
+}
+
+// Writes one of the generated files to path, or to the same place in the fallback output path if that fails.
+fn write_output(config: &AtomConfig, path: &Path, contents: &str) -> Result<(), WriteError> {
+    let error = match write_creating_directories(path, contents) {
+        Ok(written) => {
+            if written && config.serving && path.starts_with(config.output_locator.src_dir()) {
+                log::warn!(
+                    "Wrote {} into the src directory, which mdbook serve watches, so the book will be rebuilt. \
+                     Set feed_destination, manifest_destination or opensearch_destination to \"build\" to write it to the build directory instead.",
+                    path.display()
+                );
+            }
+            return Ok(());
+        }
+        Err(source) => WriteError { path: path.to_path_buf(), source },
+    };
+    let fallback_path = match config.fallback_output_path.as_ref() {
+        Some(fallback_output_path) => fallback_output_path.join(path.strip_prefix(config.output_locator.src_dir()).unwrap_or(path)),
+        None => return Err(error),
+    };
+    log::warn!("{}, writing it to {} instead", error, fallback_path.display());
+    write_creating_directories(&fallback_path, contents)
+        .map(|_| ())
+        .map_err(|source| WriteError { path: fallback_path, source })
+}
+
+// Returns whether the file was written, which it isn't when it already has contents.
+fn write_creating_directories(path: &Path, contents: &str) -> io::Result<bool> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_if_changed(path, contents)
+}
+
+// Follows redirects from url, a page of the book at base_url. Redirect targets are relative to the page they're from,
+// and targets outside of the book are used as they are.
+fn redirected_url(url: &str, redirects: &BTreeMap<String, String>, base_url: &Url) -> String {
+    let original_url = url;
+    let mut url = url.to_string();
+    let mut visited: HashSet<String> = HashSet::new();
+    while let Some(page) = url.strip_prefix(base_url.as_str()).map(|page| format!("/{}", page)) {
+        let target = match redirects.get(&page) {
+            Some(target) => target,
+            None => break,
+        };
+        if !visited.insert(page) {
+            log::warn!("The redirects from {} loop, linking to {}", original_url, url);
+            break;
+        }
+        url = match Url::parse(target) {
+            Ok(_) => target.to_string(),
+            Err(_) => match Url::parse(&url).and_then(|page_url| page_url.join(target)) {
+                Ok(target_url) => target_url.to_string(),
+                Err(_) => target.to_string(),
+            },
+        };
+    }
+    url
+}
+
+impl Default for AtomGenerator {
+    fn default() -> Self {
+        AtomGenerator::new()
     }
 }
 
 impl AtomGenerator {
-    fn generate(&self, posts: Vec<Post>, title: String, base_url: Url) -> atom_syndication::Feed {
+    // Uses the same defaults as the preprocessor.
+    pub fn new() -> AtomGenerator {
+        AtomGenerator {
+            title_template: "{title}".to_string(),
+            entry_authors: EntryAuthors::All,
+            author_limit: AuthorLimit::default(),
+            feed_contributors: true,
+            thumbnails: true,
+            language: None,
+            include_source: false,
+            xml_base: false,
+            entry_order: EntryOrder::NewestFirst,
+            machine_metadata: false,
+            feed_url: None,
+        }
+    }
+
+    pub fn with_author_limit(mut self, author_limit: AuthorLimit) -> AtomGenerator {
+        self.author_limit = author_limit;
+        self
+    }
+
+    pub fn with_entry_order(mut self, entry_order: EntryOrder) -> AtomGenerator {
+        self.entry_order = entry_order;
+        self
+    }
+
+    // Adds each post's source path, commits and number of revisions to its entry as mdbook-git-atom:sourcePath,
+    // mdbook-git-atom:lastCommit, mdbook-git-atom:createdCommit and mdbook-git-atom:revisionCount elements.
+    pub fn with_machine_metadata(mut self, machine_metadata: bool) -> AtomGenerator {
+        self.machine_metadata = machine_metadata;
+        self
+    }
+
+    // Fails when there are no posts, since a feed without entries is usually a mistake. empty_feed makes one anyway.
+    pub fn generate(&self, posts: Vec<Post>, title: String, base_url: Url) -> Result<atom_syndication::Feed, AtomError> {
+        if posts.is_empty() {
+            return Err(AtomError::NoPosts);
+        }
+        Ok(self.feed(posts, title, base_url))
+    }
+
+    // A valid feed without any entries, updated now.
+    pub fn empty_feed(&self, title: String, base_url: Url) -> atom_syndication::Feed {
+        self.feed(vec![], title, base_url)
+    }
+
+    fn feed(&self, mut posts: Vec<Post>, title: String, base_url: Url) -> atom_syndication::Feed {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("generate_feed", entries = posts.len()).entered();
+
+        // Posts were sorted to pick which ones are in the feed, which isn't necessarily the order readers expect.
+        // Posts updated at the same time stay in that order.
+        let direction = match self.entry_order {
+            EntryOrder::NewestFirst => SortDirection::Descending,
+            EntryOrder::OldestFirst => SortDirection::Ascending,
+        };
+        sort_posts(&mut posts, SortField::LastModified, direction);
+
         let entries: Vec<atom_syndication::Entry> = posts
             .iter()
-            .filter_map(|p| p.to_atom_entry(&base_url))
+            .filter_map(|p| {
+                let mut entry = p.to_atom_entry(&base_url, self.entry_title(p, &title), p.authors_vector(&self.entry_authors, &self.author_limit), self.thumbnails, self.language.as_deref(), self.xml_base)?;
+                if self.machine_metadata {
+                    p.add_machine_metadata(&mut entry.extensions);
+                }
+                Some(entry)
+            })
             .collect();
 
         eprintln!("created {} entries", entries.len());
 
-        if posts.is_empty() {
-            panic!("No posts? How?");
+        let mut namespaces = BTreeMap::from([(GIT_ATOM_PREFIX.to_string(), GIT_ATOM_NAMESPACE.to_string())]);
+        if self.thumbnails {
+            namespaces.insert(MEDIA_PREFIX.to_string(), MEDIA_NAMESPACE.to_string());
         }
 
-        atom_syndication::Feed {
+        let authors: Vec<atom_syndication::Person> = vec![];
+        let contributors = if self.feed_contributors {
+            self.contributors(&posts, &authors)
+        } else {
+            vec![]
+        };
+
+        let mut feed = atom_syndication::Feed {
             title: atom_syndication::Text {
                 value: title,
                 base: None,
-                lang: None,
+                lang: self.language.clone(),
                 r#type: Default::default()
             },
-            id: "".to_string(),
-            updated: fixed_date_time_from_timestamp(
-                &posts
-                    .get(0)
-                    .expect("No posts to get a last updated at from")
-                    .last_modified_date
-            ),
-            authors: vec![],
+            // The book's url identifies the feed, which entries' source elements refer back to. Books with a feed for
+            // each translation identify each one by its own url instead.
+            id: self.feed_url.as_ref().unwrap_or(&base_url).to_string(),
+            // Posts aren't necessarily sorted by modification date, so look for the latest one.
+            updated: posts
+                .iter()
+                .map(|post| post.last_modified_date)
+                .max()
+                .map(|updated| fixed_date_time_from_timestamp(&updated))
+                .unwrap_or_else(|| fixed_date_time_from_timestamp(&Time::new(chrono::Utc::now().timestamp(), 0))),
+            authors,
             categories: vec![],
-            contributors: vec![],
+            contributors,
             generator: None,
             icon: None,
             links: vec![],
@@ -126,10 +1046,130 @@ impl AtomGenerator {
             rights: None,
             subtitle: None,
             entries,
-            extensions: Default::default(),
-            namespaces: Default::default()
+            // Lets consumers notice when the feed's format changes between versions.
+            extensions: BTreeMap::from([(
+                GIT_ATOM_PREFIX.to_string(),
+                BTreeMap::from([("version".to_string(), vec![git_atom_extension("version", env!("CARGO_PKG_VERSION").to_string())])]),
+            )]),
+            namespaces
+        };
+
+        // Aggregators that copy entries into other feeds can still tell which book they came from.
+        if self.include_source {
+            let source = atom_syndication::Source {
+                title: feed.title.clone(),
+                id: feed.id.to_string(),
+                updated: feed.updated,
+                authors: feed.authors.clone(),
+                categories: feed.categories.clone(),
+                contributors: feed.contributors.clone(),
+                generator: feed.generator.clone(),
+                icon: feed.icon.clone(),
+                links: feed.links.clone(),
+                logo: feed.logo.clone(),
+                rights: feed.rights.clone(),
+                subtitle: feed.subtitle.clone(),
+            };
+            for entry in feed.entries.iter_mut() {
+                entry.source = Some(source.clone());
+            }
+        }
+        feed
+    }
+}
+
+impl AtomGenerator {
+    // Describes the posts the same way their feed entries do, for consumers that would rather not parse the feed.
+    fn manifest<'a>(&self, posts: &'a [Post], book_title: &str, base_url: &Url) -> Vec<ManifestEntry<'a>> {
+        posts.iter()
+            .filter_map(|post| {
+                Some(ManifestEntry {
+                    id: &post.id,
+                    title: self.entry_title(post, book_title),
+                    url: post.source_url(Some(base_url))?,
+                    published: fixed_date_time_from_timestamp(&post.created_date).to_rfc3339(),
+                    updated: fixed_date_time_from_timestamp(&post.last_modified_date).to_rfc3339(),
+                    // Only real authors, without the one standing in for those left out of the entry.
+                    authors: post.limited_authors(&self.entry_authors, &self.author_limit).0.iter().map(|author| author.name.as_str()).collect(),
+                    section: post.section.as_deref(),
+                    summary: post.content.as_deref(),
+                })
+            })
+            .collect()
+    }
+
+    // Everyone who authored any of the posts, sorted and without the feed's own authors.
+    fn contributors(&self, posts: &[Post], feed_authors: &[atom_syndication::Person]) -> Vec<atom_syndication::Person> {
+        posts.iter()
+            .flat_map(|post| post.authors.keys())
+            .collect::<BTreeSet<&Author>>()
+            .into_iter()
+            .map(|author| author.as_person())
+            .filter(|person| !feed_authors.iter().any(|feed_author| feed_author.name == person.name && feed_author.email == person.email))
+            .collect()
+    }
+
+    fn entry_title(&self, post: &Post, book_title: &str) -> String {
+        self.title_template
+            .replace("{book}", book_title)
+            .replace("{section}", post.section.as_deref().unwrap_or(""))
+            .replace("{title}", &post.title)
+    }
+}
+
+// Compares the previously generated feed against the ids of the current entries. Entries that disappeared are deleted
+// as of head_time, and earlier tombstones are kept until they're too old or their id is used again.
+fn deleted_entries(feed_path: &Path, current_ids: &HashSet<&str>, head_time: Time, max_age_days: i64) -> Vec<Extension> {
+    let previous_feed = match fs::File::open(feed_path).ok().and_then(|file| atom_syndication::Feed::read_from(BufReader::new(file)).ok()) {
+        Some(feed) => feed,
+        None => return vec![],
+    };
+    let deleted_at = fixed_date_time_from_timestamp(&head_time);
+
+    let previous_tombstones = previous_feed.extensions
+        .get(TOMBSTONES_PREFIX)
+        .and_then(|extensions| extensions.get("deleted-entry"))
+        .cloned()
+        .unwrap_or_default();
+    let mut tombstones: Vec<Extension> = previous_tombstones.into_iter()
+        .filter(|tombstone| {
+            let id = tombstone.attrs.get("ref").map(|id| id.as_str()).unwrap_or("");
+            let when = tombstone.attrs.get("when").and_then(|when| chrono::DateTime::parse_from_rfc3339(when).ok());
+            let expired = match when {
+                Some(when) => max_age_days > 0 && deleted_at.signed_duration_since(when) > chrono::Duration::days(max_age_days),
+                None => true,
+            };
+            !id.is_empty() && !current_ids.contains(id) && !expired
+        })
+        .collect();
+
+    for entry in previous_feed.entries.iter() {
+        let already_deleted = tombstones.iter().any(|tombstone| tombstone.attrs.get("ref") == Some(&entry.id));
+        if !current_ids.contains(entry.id.as_str()) && !already_deleted {
+            tombstones.push(Extension {
+                name: format!("{}:deleted-entry", TOMBSTONES_PREFIX),
+                value: None,
+                attrs: BTreeMap::from([
+                    ("ref".to_string(), entry.id.to_string()),
+                    ("when".to_string(), deleted_at.to_rfc3339()),
+                ]),
+                children: Default::default(),
+            });
         }
     }
+    tombstones
+}
+
+#[derive(Serialize)]
+struct ManifestEntry<'a> {
+    id: &'a str,
+    title: String,
+    url: String,
+    published: String,
+    updated: String,
+    authors: Vec<&'a str>,
+    section: Option<&'a str>,
+    summary: Option<&'a str>,
 }
 
 fn fixed_date_time_from_timestamp(timestamp: &Time) -> chrono::DateTime<FixedOffset> {
@@ -138,6 +1178,29 @@ fn fixed_date_time_from_timestamp(timestamp: &Time) -> chrono::DateTime<FixedOff
     chrono::DateTime::<FixedOffset>::from_utc(naive, chrono::FixedOffset::east(0))
 }
 
+fn image_mime_type(path: &str) -> Option<&'static str> {
+    let extension = path.rsplit('.').next()?.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "avif" => Some("image/avif"),
+        "bmp" => Some("image/bmp"),
+        "ico" => Some("image/x-icon"),
+        _ => None,
+    }
+}
+
+fn git_atom_extension(name: &str, value: String) -> Extension {
+    Extension {
+        name: format!("{}:{}", GIT_ATOM_PREFIX, name),
+        value: Some(value),
+        attrs: Default::default(),
+        children: Default::default(),
+    }
+}
+
 impl Author {
     fn as_person(&self) -> atom_syndication::Person {
         atom_syndication::Person {
@@ -149,53 +1212,126 @@ impl Author {
 }
 
 impl Post {
-    fn authors_vector(&self) -> Vec<atom_syndication::Person> {
-        self.authors.iter()
+    fn authors_vector(&self, entry_authors: &EntryAuthors, author_limit: &AuthorLimit) -> Vec<atom_syndication::Person> {
+        let (authors, overflow_author) = self.limited_authors(entry_authors, author_limit);
+        authors.into_iter()
             .map(|author| author.as_person())
+            .chain(overflow_author.map(|name| atom_syndication::Person { name, email: None, uri: None }))
             .collect()
     }
 
-    fn link(&self, base_url: &Url) -> Option<atom_syndication::Link> {
-        if let Some(url_string) = self.source_url(Some(base_url)) {
-            Some(Link {
-                href: url_string,
-                rel: "self".to_string(),
-                hreflang: None,
-                mime_type: None,
-                title: None,
-                length: None
-            })
-        } else {
-            None
+    fn link(&self, base_url: &Url, language: Option<&str>) -> Option<atom_syndication::Link> {
+        self.source_url(Some(base_url)).map(|url_string| Link {
+            href: url_string,
+            rel: "self".to_string(),
+            hreflang: language.map(|language| language.to_string()),
+            mime_type: None,
+            title: None,
+            length: None
+        })
+    }
+
+    // Absolute url of the chapter's first image, resolved against the chapter's location.
+    fn image_url(&self, base_url: &Url) -> Option<Url> {
+        let image = self.image.as_ref()?;
+        match Url::parse(image) {
+            Ok(url) => Some(url),
+            Err(_) => base_url.join(self.path.to_str()?).ok()?.join(image).ok(),
         }
     }
 
-    fn to_atom_entry(&self, base_url: &Url) -> Option<atom_syndication::Entry> {
+    fn enclosure(&self, image_url: &Url) -> Link {
+        Link {
+            href: image_url.to_string(),
+            rel: "enclosure".to_string(),
+            hreflang: None,
+            mime_type: image_mime_type(image_url.path()).map(|mime_type| mime_type.to_string()),
+            title: None,
+            length: None
+        }
+    }
+
+    fn extensions(&self, image_url: Option<&Url>) -> ExtensionMap {
+        let mut elements: BTreeMap<String, Vec<Extension>> = BTreeMap::new();
+        if let Some(word_count) = self.word_count {
+            elements.insert("wordCount".to_string(), vec![git_atom_extension("wordCount", word_count.to_string())]);
+        }
+        if let Some(reading_time) = self.reading_time {
+            elements.insert("readingTime".to_string(), vec![git_atom_extension("readingTime", reading_time.to_string())]);
+        }
+        if self.churn > 0 {
+            elements.insert("churn".to_string(), vec![git_atom_extension("churn", self.churn.to_string())]);
+        }
+
+        let mut extensions = ExtensionMap::new();
+        if !elements.is_empty() {
+            extensions.insert(GIT_ATOM_PREFIX.to_string(), elements);
+        }
+        if let Some(image_url) = image_url {
+            let thumbnail = Extension {
+                name: format!("{}:thumbnail", MEDIA_PREFIX),
+                value: None,
+                attrs: BTreeMap::from([("url".to_string(), image_url.to_string())]),
+                children: Default::default(),
+            };
+            extensions.insert(MEDIA_PREFIX.to_string(), BTreeMap::from([("thumbnail".to_string(), vec![thumbnail])]));
+        }
+        extensions
+    }
+
+    // Elements that map the entry back to its file and commits, for tools like release notes generators. Posts without
+    // git history have no commits to add.
+    fn add_machine_metadata(&self, extensions: &mut ExtensionMap) {
+        let source_path = self.source_path.as_ref().and_then(|source_path| source_path.to_str()).map(|source_path| source_path.replace('\\', "/"));
+        let metadata = [
+            ("sourcePath", source_path),
+            ("lastCommit", self.last_commit.clone()),
+            ("createdCommit", self.created_commit.clone()),
+            ("revisionCount", Some(self.revision_count).filter(|revisions| *revisions > 0).map(|revisions| revisions.to_string())),
+        ];
+        for (name, value) in metadata {
+            if let Some(value) = value {
+                extensions.entry(GIT_ATOM_PREFIX.to_string()).or_default()
+                    .insert(name.to_string(), vec![git_atom_extension(name, value)]);
+            }
+        }
+    }
+
+    fn to_atom_entry(&self, base_url: &Url, title: String, authors: Vec<atom_syndication::Person>, thumbnails: bool, language: Option<&str>, xml_base: bool) -> Option<atom_syndication::Entry> {
+        let image_url = if thumbnails { self.image_url(base_url) } else { None };
+        let link = self.link(base_url, language)?;
+        let content_base = if xml_base { Some(base_url.to_string()) } else { None };
+        let mut links = vec![link];
+        if let Some(image_url) = image_url.as_ref() {
+            links.push(self.enclosure(image_url));
+        }
+
         Some(atom_syndication::Entry {
             title: atom_syndication::Text {
-                value: self.title.to_string(),
+                value: title,
                 base: None,
-                lang: None,
+                lang: language.map(|language| language.to_string()),
                 r#type: Default::default()
             },
             id: self.id.to_string(),
             updated: fixed_date_time_from_timestamp(&self.last_modified_date),
-            authors: self.authors_vector(),
+            authors,
             categories: vec![],
             contributors: vec![],
-            links: vec![self.link(base_url)?],
+            links,
             published: Some(fixed_date_time_from_timestamp(&self.created_date)),
-            rights: None,
+            rights: self.rights.as_ref().map(|rights| atom_syndication::Text::plain(rights.as_str())),
             source: None,
             summary: None,
             content: Some(atom_syndication::Content {
-                base: None,
-                lang: None,
+                base: content_base,
+                lang: language.map(|language| language.to_string()),
+                // atom_syndication writes the value as it is, so the html has to be escaped exactly once to be text.
                 value: Some(html_escape::encode_text(&self.content.as_ref().unwrap_or(&"".to_string())).to_string()),
                 src: None,
                 content_type: Some("html".to_string())
             }),
-            extensions: Default::default()
+            extensions: self.extensions(image_url.as_ref())
         })
     }
 }
\ No newline at end of file
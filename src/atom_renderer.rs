@@ -0,0 +1,37 @@
+use mdbook::errors::Error;
+use mdbook::renderer::{RenderContext, Renderer};
+use crate::atom_processor::{AtomConfig, AtomProcessor};
+use crate::errors::ConfigError;
+use crate::output_locator::OutputLocator;
+
+// Writes the atom feed as an mdbook renderer, configured from the [output.git-atom] section, instead of from a
+// preprocessor. The feed ends up in the renderer's own directory in the build directory, next to the html, rather than
+// in the src directory. It takes the same options as the preprocessor, apart from renderers.
+pub struct AtomRenderer;
+
+impl Renderer for AtomRenderer {
+    fn name(&self) -> &str {
+        "git-atom"
+    }
+
+    fn render(&self, ctx: &RenderContext) -> Result<(), Error> {
+        let section_config = match ctx.config.get(&format!("output.{}", self.name())) {
+            Some(toml::Value::Table(section_config)) => section_config,
+            _ => return Err(ConfigError::MissingOutputSection(self.name().to_string()).into()),
+        };
+        let mut config = AtomConfig::from_section(&ctx.root, &ctx.config, section_config)?;
+        // mdbook runs renderers in their destination, so chapters are read from the book root by its full path.
+        config.content_path = ctx.root.join(&config.content_path);
+        config.output_locator = OutputLocator::in_directory(&ctx.destination);
+
+        let post_finder = config.post_finder();
+        post_finder.open()?;
+        if !post_finder.has_history() {
+            log::warn!("No git history found, skipping atom feed generation");
+            return Ok(());
+        }
+        let all_posts = config.search(&post_finder, &ctx.book)?;
+        AtomProcessor::write_feed(&config, &ctx.book, all_posts, post_finder.head_time())?;
+        Ok(())
+    }
+}
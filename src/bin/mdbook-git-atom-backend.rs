@@ -0,0 +1,19 @@
+use std::process;
+use clap::App;
+use mdbook_git_atom::library_helpers;
+use mdbook_git_atom::atom_renderer::AtomRenderer;
+
+pub fn make_app() -> App<'static, 'static> {
+    App::new("mdbook-git-atom-backend")
+        .about("A renderer that writes an atom feed to its own output directory. Configured with [output.git-atom]")
+        .after_help(library_helpers::EXIT_CODES_HELP)
+}
+
+fn main() {
+    library_helpers::init_logging();
+    make_app().get_matches();
+    if let Err(e) = library_helpers::handle_rendering(&AtomRenderer) {
+        eprintln!("{}", e);
+        process::exit(library_helpers::exit_code(&e));
+    }
+}
@@ -1,3 +1,4 @@
+use std::process;
 use clap::{App, Arg, SubCommand};
 use mdbook_git_atom::library_helpers;
 use mdbook_git_atom::atom_processor::AtomProcessor;
@@ -10,15 +11,27 @@ pub fn make_app() -> App<'static, 'static> {
                 .arg(Arg::with_name("renderer").required(true))
                 .about("Check whether a renderer is supported by this preprocessor"),
         )
+        .subcommand(
+            SubCommand::with_name("check")
+                .arg(Arg::with_name("feed").required(true).help("The atom.xml to check"))
+                .arg(Arg::with_name("strict").long("strict").help("Also check that entries have authors and their ids are IRIs"))
+                .about("Check a generated feed for problems. Exits with 1 if there are warnings and 2 if there are errors"),
+        )
+        .after_help(library_helpers::EXIT_CODES_HELP)
 }
 
 fn main() {
+    library_helpers::init_logging();
     let matches = make_app().get_matches();
-    let preprocessor = AtomProcessor;
+    let preprocessor = AtomProcessor::new();
     if let Some(sub_args) = matches.subcommand_matches("supports") {
         library_helpers::handle_supports(&preprocessor, sub_args);
     }
+    if let Some(sub_args) = matches.subcommand_matches("check") {
+        library_helpers::handle_check(sub_args);
+    }
     if let Err(e) = library_helpers::handle_preprocessing(&preprocessor) {
         eprintln!("{}", e);
+        process::exit(library_helpers::exit_code(&e));
     }
 }
\ No newline at end of file
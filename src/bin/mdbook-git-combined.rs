@@ -0,0 +1,28 @@
+use std::process;
+use clap::{App, Arg, SubCommand};
+use mdbook_git_atom::library_helpers;
+use mdbook_git_atom::combined_processor::CombinedProcessor;
+
+pub fn make_app() -> App<'static, 'static> {
+    App::new("mdbook-git-combined")
+        .about("A preprocessor that generates an atom feed and replaces {{#recently_updated}} from a single search of the repo.")
+        .subcommand(
+            SubCommand::with_name("supports")
+                .arg(Arg::with_name("renderer").required(true))
+                .about("Check whether a renderer is supported by this preprocessor"),
+        )
+        .after_help(library_helpers::EXIT_CODES_HELP)
+}
+
+fn main() {
+    library_helpers::init_logging();
+    let matches = make_app().get_matches();
+    let preprocessor = CombinedProcessor;
+    if let Some(sub_args) = matches.subcommand_matches("supports") {
+        library_helpers::handle_supports(&preprocessor, sub_args);
+    }
+    if let Err(e) = library_helpers::handle_preprocessing(&preprocessor) {
+        eprintln!("{}", e);
+        process::exit(library_helpers::exit_code(&e));
+    }
+}
\ No newline at end of file
@@ -1,3 +1,4 @@
+use std::process;
 use clap::{App, Arg, SubCommand};
 use mdbook_git_atom::library_helpers;
 use mdbook_git_atom::updated_processor::UpdatedProcessor;
@@ -10,15 +11,18 @@ pub fn make_app() -> App<'static, 'static> {
                 .arg(Arg::with_name("renderer").required(true))
                 .about("Check whether a renderer is supported by this preprocessor"),
         )
+        .after_help(library_helpers::EXIT_CODES_HELP)
 }
 
 fn main() {
+    library_helpers::init_logging();
     let matches = make_app().get_matches();
-    let preprocessor = UpdatedProcessor;
+    let preprocessor = UpdatedProcessor::new();
     if let Some(sub_args) = matches.subcommand_matches("supports") {
         library_helpers::handle_supports(&preprocessor, sub_args);
     }
     if let Err(e) = library_helpers::handle_preprocessing(&preprocessor) {
         eprintln!("{}", e);
+        process::exit(library_helpers::exit_code(&e));
     }
 }
\ No newline at end of file
@@ -0,0 +1,52 @@
+use mdbook::book::Book;
+use mdbook::errors::Error;
+use mdbook::preprocess::{Preprocessor, PreprocessorContext};
+use crate::atom_processor::{AtomConfig, AtomProcessor};
+use crate::updated_processor::{UpdatedConfig, UpdatedProcessor};
+
+// Generates the atom feed and replaces the recently updated directives from a single search of the repository.
+// Both are configured from the [preprocessor.git-combined] section, and posts are found using the atom feed's options.
+pub struct CombinedProcessor;
+
+impl Preprocessor for CombinedProcessor {
+    fn name(&self) -> &str {
+        "git-combined"
+    }
+
+    fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book, Error> {
+        let atom_config = AtomConfig::from_book_config(ctx, self.name())?;
+        let mut updated_config = UpdatedConfig::from_book_config(ctx, self.name())?;
+        // The feed is written by this preprocessor, so its url doesn't have to come from the env file.
+        updated_config.feed_url = Some(atom_config.feed_url(None).to_string());
+        if !updated_config.renderers.contains(&ctx.renderer) {
+            UpdatedProcessor::new().strip_directives(&mut book);
+            return Ok(book);
+        }
+
+        let post_finder = atom_config.post_finder()
+            .with_all_branches(updated_config.consider_all_branches);
+        post_finder.open()?;
+        let all_posts = if post_finder.has_history() {
+            atom_config.search(&post_finder, &book)?
+        } else {
+            log::warn!("No git history found, skipping atom feed generation");
+            vec![]
+        };
+
+        let statistics = post_finder.statistics_for_posts(&all_posts);
+        let author_statistics = post_finder.author_statistics(&book, &atom_config.content_path);
+        UpdatedProcessor::new().process_book(&mut book, &all_posts, (&statistics, &author_statistics), &updated_config)?;
+
+        // Like the atom preprocessor, the feed is only written for its own renderers.
+        if post_finder.has_history() && atom_config.renderers.contains(&ctx.renderer) {
+            AtomProcessor::write_feed(&atom_config, &book, all_posts, post_finder.head_time())?;
+        }
+
+        Ok(book)
+    }
+
+    // Renderers that aren't in renderers get their directives stripped, which needs the context, so run checks it.
+    fn supports_renderer(&self, _renderer: &str) -> bool {
+        true
+    }
+}
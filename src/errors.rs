@@ -0,0 +1,152 @@
+use std::{fmt, io};
+use std::path::PathBuf;
+use git2::Oid;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    MissingSection(String),
+    MissingOutputSection(String),
+    MissingBaseUrl,
+    InvalidBaseUrl(String),
+    BaseUrlMustBeHttp(String),
+    MissingTitle,
+    InvalidValue { key: String, message: String },
+}
+
+impl ConfigError {
+    pub(crate) fn invalid_value(key: &str, message: String) -> ConfigError {
+        ConfigError::InvalidValue { key: key.to_string(), message }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::MissingSection(name) => write!(f, "No [preprocessor.{}] section in book.toml", name),
+            ConfigError::MissingOutputSection(name) => write!(f, "No [output.{}] section in book.toml", name),
+            ConfigError::MissingBaseUrl => write!(f, "No base_url specified"),
+            ConfigError::InvalidBaseUrl(base_url) => write!(f, "Invalid base_url: {}", base_url),
+            ConfigError::BaseUrlMustBeHttp(base_url) => write!(
+                f,
+                "base_url must be an absolute http or https url, like \"https://example.com/my-book/\", but was {}",
+                base_url
+            ),
+            ConfigError::MissingTitle => write!(f, "The book has no title"),
+            ConfigError::InvalidValue { key, message } => write!(f, "Invalid {}: {}", key, message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum SearchError {
+    // The repository has no commits, so nothing has a last modified date.
+    NoHistory,
+    // Every chapter was skipped, so there are no posts.
+    EmptyResult,
+    // The book's src directory isn't in the repository, so none of its chapters have any history.
+    ContentPathOutsideRepository(PathBuf),
+    // The first commit of a range to search isn't an ancestor of the second, or either isn't in the repository.
+    InvalidCommitRange(Oid, Oid),
+    // A chapter couldn't be made into a post, like when it can't be read.
+    Chapter(PostError),
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::NoHistory => write!(f, "The repository has no commits"),
+            SearchError::EmptyResult => write!(f, "No chapters with git history were found, run with RUST_LOG=info to see why"),
+            SearchError::ContentPathOutsideRepository(content_path) => write!(f, "The book's source directory {} isn't inside the git repository", content_path.display()),
+            SearchError::InvalidCommitRange(from, to) => write!(f, "{} isn't an ancestor of {} in the git repository", from, to),
+            SearchError::Chapter(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SearchError::Chapter(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum PostError {
+    // The repository couldn't be opened, or the file couldn't be blamed.
+    Git(git2::Error),
+    // The chapter at the path has no history to date it by, like when it doesn't exist.
+    NoHistory(PathBuf),
+    // The chapter at the path couldn't be read.
+    Read { path: PathBuf, source: io::Error },
+    // The chapter at the path isn't valid UTF-8, and invalid_utf8 is "error".
+    InvalidUtf8(PathBuf),
+}
+
+impl fmt::Display for PostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PostError::Git(error) => write!(f, "Unable to blame the chapter: {}", error),
+            PostError::NoHistory(path) => write!(f, "{} has no history", path.display()),
+            PostError::Read { path, source } => write!(f, "Unable to read {}: {}", path.display(), source),
+            PostError::InvalidUtf8(path) => write!(f, "{} isn't valid UTF-8, set invalid_utf8 = \"lossy\" or \"skip\" to publish it anyway", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for PostError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PostError::Git(error) => Some(error),
+            PostError::Read { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<git2::Error> for PostError {
+    fn from(error: git2::Error) -> Self {
+        PostError::Git(error)
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum AtomError {
+    // There were no posts to make entries from, like when every chapter is excluded or too old.
+    NoPosts,
+}
+
+impl fmt::Display for AtomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtomError::NoPosts => write!(f, "There are no chapters to put in the atom feed, set allow_empty_feed = true to write an empty feed instead"),
+        }
+    }
+}
+
+impl std::error::Error for AtomError {}
+
+// One of the generated files couldn't be written.
+#[derive(Debug)]
+pub struct WriteError {
+    pub path: PathBuf,
+    pub source: io::Error,
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unable to write {}: {}", self.path.display(), self.source)
+    }
+}
+
+impl std::error::Error for WriteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
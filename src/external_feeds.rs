@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use atom_syndication::{Entry, Feed};
+
+// Reads every feed in sources, which are urls or paths relative to the book root.
+// Feeds that can't be loaded are skipped with a warning, so the book's own entries are still published.
+pub(crate) fn load_external_feeds(sources: &[String], root_path: &Path, offline: bool) -> Vec<Feed> {
+    sources.iter()
+        .filter_map(|source| {
+            let feed = if source.starts_with("http://") || source.starts_with("https://") {
+                if offline {
+                    log::info!("Offline, not fetching {}", source);
+                    return None;
+                }
+                fetch_feed(source)
+            } else {
+                File::open(root_path.join(source))
+                    .map_err(|error| error.to_string())
+                    .and_then(|file| Feed::read_from(BufReader::new(file)).map_err(|error| error.to_string()))
+            };
+            feed.map_err(|error| log::warn!("Unable to merge {}: {}", source, error)).ok()
+        })
+        .collect()
+}
+
+#[cfg(feature = "remote-feeds")]
+fn fetch_feed(url: &str) -> Result<Feed, String> {
+    let response = ureq::get(url).call().map_err(|error| error.to_string())?;
+    Feed::read_from(BufReader::new(response.into_reader())).map_err(|error| error.to_string())
+}
+
+#[cfg(not(feature = "remote-feeds"))]
+fn fetch_feed(_url: &str) -> Result<Feed, String> {
+    Err("fetching feeds requires the remote-feeds feature".to_string())
+}
+
+// Adds the entries of external feeds to feed, newest first and limited to target_entries. When the same id is in
+// more than one feed, the most recently updated entry is kept.
+pub(crate) fn merge_external_feeds(feed: &mut Feed, external_feeds: &[Feed], target_entries: i64) {
+    if external_feeds.is_empty() {
+        return;
+    }
+
+    let mut entries: HashMap<String, Entry> = HashMap::new();
+    let external_entries = external_feeds.iter().flat_map(|external_feed| external_feed.entries.iter().cloned());
+    for entry in feed.entries.drain(..).chain(external_entries) {
+        match entries.get(&entry.id) {
+            Some(existing) if existing.updated >= entry.updated => (),
+            _ => {
+                entries.insert(entry.id.to_string(), entry);
+            }
+        }
+    }
+    let mut entries: Vec<Entry> = entries.into_values().collect();
+    entries.sort_by(|a, b| b.updated.cmp(&a.updated).then_with(|| a.id.cmp(&b.id)));
+    if target_entries > 0 {
+        entries.truncate(target_entries as usize);
+    }
+
+    // Extensions in the external entries need their namespaces declared.
+    for external_feed in external_feeds {
+        for (prefix, namespace) in external_feed.namespaces.iter() {
+            feed.namespaces.entry(prefix.to_string()).or_insert_with(|| namespace.to_string());
+        }
+    }
+    if let Some(updated) = entries.iter().map(|entry| entry.updated).max() {
+        feed.updated = updated;
+    }
+    feed.entries = entries;
+}
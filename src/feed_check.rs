@@ -0,0 +1,119 @@
+use std::fmt;
+use std::io::BufReader;
+use atom_syndication::{Entry, Feed};
+use chrono::DateTime;
+use regex::Regex;
+use url::Url;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    Warning,
+    Error,
+}
+
+pub(crate) struct Violation {
+    pub(crate) severity: Severity,
+    // The line the violation is on, counting from 1, when it's known.
+    pub(crate) line: Option<usize>,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+// Checks a feed for the requirements of RFC 4287 that readers most often rely on. Strict checks are reported as
+// warnings, since plenty of readers cope without them.
+pub(crate) fn check_feed(contents: &str, strict: bool) -> Vec<Violation> {
+    let mut violations = check_dates(contents);
+    if !violations.is_empty() {
+        // Invalid dates stop the feed from being parsed at all.
+        return violations;
+    }
+
+    let feed = match Feed::read_from(BufReader::new(contents.as_bytes())) {
+        Ok(feed) => feed,
+        Err(error) => {
+            violations.push(Violation { severity: Severity::Error, line: None, message: format!("Unable to parse the feed: {}", error) });
+            return violations;
+        }
+    };
+
+    let feed_line = line_of_element(contents, "feed", 0);
+    let mut error = |line: Option<usize>, message: String| violations.push(Violation { severity: Severity::Error, line, message });
+    if feed.id.trim().is_empty() {
+        error(feed_line, "The feed has no id".to_string());
+    }
+    if feed.title.value.trim().is_empty() {
+        error(feed_line, "The feed has no title".to_string());
+    }
+    for (index, entry) in feed.entries.iter().enumerate() {
+        let entry_line = line_of_element(contents, "entry", index);
+        if entry.id.trim().is_empty() {
+            error(entry_line, format!("Entry {} has no id", describe(entry, index)));
+        }
+        if entry.title.value.trim().is_empty() {
+            error(entry_line, format!("Entry {} has no title", describe(entry, index)));
+        }
+        if entry.links.is_empty() {
+            error(entry_line, format!("Entry {} has no links", describe(entry, index)));
+        }
+    }
+
+    // Feeds only should link to themselves, so a missing link is just a warning.
+    if feed.links.is_empty() {
+        violations.push(Violation { severity: Severity::Warning, line: feed_line, message: "The feed has no links".to_string() });
+    }
+
+    if strict {
+        for (index, entry) in feed.entries.iter().enumerate() {
+            let entry_line = line_of_element(contents, "entry", index);
+            let mut warning = |message: String| violations.push(Violation { severity: Severity::Warning, line: entry_line, message });
+            if entry.authors.is_empty() && feed.authors.is_empty() {
+                warning(format!("Entry {} has no author, and neither does the feed", describe(entry, index)));
+            }
+            if !entry.id.trim().is_empty() && Url::parse(&entry.id).is_err() {
+                warning(format!("Entry {} has an id that isn't an IRI", describe(entry, index)));
+            }
+        }
+    }
+
+    violations
+}
+
+// Atom dates have to be RFC 3339 timestamps, like 2003-12-13T18:30:02Z.
+fn check_dates(contents: &str) -> Vec<Violation> {
+    let date_regex = Regex::new(r"<(?P<element>updated|published)>(?P<date>[^<]*)</(?:updated|published)>").unwrap();
+    date_regex.captures_iter(contents)
+        .filter(|captures| DateTime::parse_from_rfc3339(captures["date"].trim()).is_err())
+        .map(|captures| Violation {
+            severity: Severity::Error,
+            line: Some(line_at(contents, captures.get(0).unwrap().start())),
+            message: format!("<{}> has an invalid date \"{}\"", &captures["element"], &captures["date"]),
+        })
+        .collect()
+}
+
+// The line of the index-th element with the given name, counting from 0.
+fn line_of_element(contents: &str, name: &str, index: usize) -> Option<usize> {
+    let element_regex = Regex::new(&format!(r"<{}[\s>/]", name)).unwrap();
+    let element = element_regex.find_iter(contents).nth(index)?;
+    Some(line_at(contents, element.start()))
+}
+
+fn line_at(contents: &str, offset: usize) -> usize {
+    contents[..offset].matches('\n').count() + 1
+}
+
+fn describe(entry: &Entry, index: usize) -> String {
+    if entry.id.trim().is_empty() {
+        format!("#{}", index + 1)
+    } else {
+        format!("\"{}\"", entry.id)
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use chrono::DateTime;
+use git2::Time;
+use pulldown_cmark::{Event, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use crate::library_helpers::write_if_changed;
+use crate::post_finder::Post;
+
+// The file in the book root content_fingerprint remembers each entry's content in. Deleting it dates every entry by
+// its latest commit again.
+pub(crate) const FINGERPRINTS_FILE: &str = ".mdbook-git-atom-fingerprints.json";
+const FINGERPRINTS_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Fingerprints {
+    version: u32,
+    // By entry id, sorted so the file only changes when an entry does.
+    entries: BTreeMap<String, FingerprintEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FingerprintEntry {
+    fingerprint: String,
+    // When the entry's content last changed, which is used as its updated date until it changes again.
+    updated: String,
+}
+
+// Dates posts whose content hasn't changed since the last build, apart from formatting, by when it last did instead of
+// by their latest commit, then remembers every post's content for the next build. Chapters are read from content_path,
+// so posts without a source file keep their dates.
+pub(crate) fn apply_fingerprints(root: &Path, content_path: &Path, posts: &mut [Post]) {
+    let path = root.join(FINGERPRINTS_FILE);
+    let previous = read_fingerprints(&path);
+    let mut entries = BTreeMap::new();
+    for post in posts.iter_mut() {
+        let markdown = match fs::read_to_string(root.join(content_path).join(&post.path)) {
+            Ok(markdown) => markdown,
+            Err(_) => continue,
+        };
+        let fingerprint = fingerprint(&normalized_content(&markdown));
+        let unchanged = previous.get(&post.id)
+            .filter(|entry| entry.fingerprint == fingerprint)
+            .and_then(|entry| parse_time(&entry.updated));
+        if let Some(updated) = unchanged {
+            post.last_modified_date = updated;
+        }
+        entries.insert(post.id.to_string(), FingerprintEntry { fingerprint, updated: format_time(&post.last_modified_date) });
+    }
+
+    let fingerprints = Fingerprints { version: FINGERPRINTS_VERSION, entries };
+    let contents = serde_json::to_string_pretty(&fingerprints).expect("Serialize fingerprints") + "\n";
+    // Unchanged entries keep their dates, so rebuilding without changes writes the same file, which is then left alone.
+    if let Err(error) = write_if_changed(&path, &contents) {
+        log::warn!("Couldn't write {}: {}", path.display(), error);
+    }
+}
+
+fn read_fingerprints(path: &Path) -> BTreeMap<String, FingerprintEntry> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return BTreeMap::new(),
+    };
+    match serde_json::from_str::<Fingerprints>(&contents) {
+        Ok(fingerprints) if fingerprints.version == FINGERPRINTS_VERSION => fingerprints.entries,
+        Ok(fingerprints) => {
+            log::warn!("Ignoring {}, which is from version {} of the format", path.display(), fingerprints.version);
+            BTreeMap::new()
+        }
+        Err(error) => {
+            log::warn!("Ignoring {}, which couldn't be read: {}", path.display(), error);
+            BTreeMap::new()
+        }
+    }
+}
+
+// The markdown without formatting that doesn't change how it reads. Whitespace outside of code blocks is collapsed to
+// single spaces, so rewrapping paragraphs doesn't change the fingerprint, while code blocks are kept exactly.
+fn normalized_content(markdown: &str) -> String {
+    let markdown = markdown.replace("\r\n", "\n");
+    let mut normalized: Vec<String> = vec![];
+    let mut prose_start = 0;
+    for (event, range) in Parser::new(&markdown).into_offset_iter() {
+        if let Event::Start(Tag::CodeBlock(_)) = event {
+            normalized.push(markdown[prose_start..range.start].split_whitespace().collect::<Vec<&str>>().join(" "));
+            normalized.push(markdown[range.clone()].to_string());
+            prose_start = range.end;
+        }
+    }
+    normalized.push(markdown[prose_start..].split_whitespace().collect::<Vec<&str>>().join(" "));
+    normalized.join("\n")
+}
+
+// 64 bit FNV-1a, which is stable across builds and Rust versions, unlike std's hashers.
+fn fingerprint(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+fn format_time(time: &Time) -> String {
+    let offset = chrono::FixedOffset::east_opt(time.offset_minutes() * 60).unwrap_or_else(|| chrono::FixedOffset::east(0));
+    DateTime::<chrono::Utc>::from_utc(chrono::NaiveDateTime::from_timestamp(time.seconds(), 0), chrono::Utc)
+        .with_timezone(&offset)
+        .to_rfc3339()
+}
+
+fn parse_time(value: &str) -> Option<Time> {
+    let date = DateTime::parse_from_rfc3339(value).ok()?;
+    Some(Time::new(date.timestamp(), date.offset().local_minus_utc() / 60))
+}
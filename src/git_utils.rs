@@ -0,0 +1,57 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use chrono::DateTime;
+use git2::{Oid, Repository, Time};
+
+// Opens the repository of the submodule containing `path`, which is relative to the root of `repo`.
+pub fn find_submodule_repo(repo: &Repository, path: &Path) -> Option<Repository> {
+    let submodules = repo.submodules().ok()?;
+    let submodule = submodules
+        .iter()
+        .find(|submodule| path.starts_with(submodule.path()))?;
+    match submodule.open() {
+        Ok(submodule_repo) => Some(submodule_repo),
+        Err(_) => {
+            log::warn!(
+                "{} is in the submodule at {}, which isn't initialized. Run `git submodule update --init` to date its chapters.",
+                path.display(), submodule.path().display()
+            );
+            None
+        }
+    }
+}
+
+// The commits at the boundary of a shallow clone, whose parents weren't fetched.
+pub fn shallow_boundary(repo: &Repository) -> HashSet<Oid> {
+    if !repo.is_shallow() {
+        return HashSet::new();
+    }
+    fs::read_to_string(repo.path().join("shallow"))
+        .map(|shallow| shallow.lines().filter_map(|line| Oid::from_str(line.trim()).ok()).collect())
+        .unwrap_or_default()
+}
+
+// The date in the note on commit in notes_ref, for the file at path relative to the root of repo. Notes are either a
+// single RFC 3339 date, for every file in the commit, or lines of "path = date" for specific files. Notes that are
+// missing or can't be parsed are ignored.
+pub fn note_date(repo: &Repository, notes_ref: &str, commit: Oid, path: &Path) -> Option<Time> {
+    let note = repo.find_note(Some(notes_ref), commit).ok()?;
+    let message = note.message()?.trim();
+    let path = path.to_str()?.replace('\\', "/");
+    let date = if message.lines().count() == 1 && !message.contains(" = ") {
+        message
+    } else {
+        message.lines()
+            .filter_map(|line| line.split_once(" = "))
+            .find(|(note_path, _)| note_path.trim() == path)?
+            .1
+    };
+    match DateTime::parse_from_rfc3339(date.trim()) {
+        Ok(date) => Some(Time::new(date.timestamp(), date.offset().local_minus_utc() / 60)),
+        Err(error) => {
+            log::debug!("Ignoring the date \"{}\" in the note on {} for {}: {}", date.trim(), commit, path, error);
+            None
+        }
+    }
+}
@@ -1,4 +1,14 @@
 pub mod atom_processor;
+pub mod atom_renderer;
+pub mod combined_processor;
+pub mod errors;
+mod external_feeds;
+mod feed_check;
+mod fingerprints;
+pub mod git_utils;
 pub mod library_helpers;
+pub mod output_locator;
 pub mod updated_processor;
-pub mod post_finder;
\ No newline at end of file
+pub mod post_finder;
+pub mod post_set;
+pub mod remote_history;
\ No newline at end of file
@@ -1,29 +1,547 @@
+use chrono::{DateTime, NaiveDate};
 use clap::ArgMatches;
+use git2::{Oid, Time};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
 use mdbook::errors::Error;
 use mdbook::preprocess::{Preprocessor, CmdPreprocessor};
-use std::{io, process};
+use mdbook::renderer::{RenderContext, Renderer};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ffi::OsString;
+use std::{fs, io, process};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use url::Url;
+use crate::atom_processor::OnMissingAsset;
+use crate::errors::ConfigError;
+use crate::feed_check::{check_feed, Severity};
+use crate::post_finder::{AuthorLimit, BlameConfig, DateOverride, EntryAuthors, InvalidUtf8, SortDirection, SortField, DEFAULT_EXCLUDED_PATHS};
+use crate::remote_history::RemoteFallback;
+
+pub const EXIT_CODES_HELP: &str = "EXIT CODES:
+    0    Success
+    1    Unexpected error
+    2    Invalid configuration in book.toml
+    3    Unable to read the git repository";
+
+pub fn init_logging() {
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "warn".into()))
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_writer(io::stderr)
+        .init();
+    #[cfg(not(feature = "tracing"))]
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+}
 
 pub fn handle_preprocessing(pre: &dyn Preprocessor) -> Result<(), Error> {
     let (ctx, book) = CmdPreprocessor::parse_input(io::stdin())?;
 
-    if ctx.mdbook_version != mdbook::MDBOOK_VERSION {
-        eprintln!(
-            "Warning: The {} plugin was built against version {} of mdbook, \
-             but we're being called from version {}",
-            pre.name(),
-            mdbook::MDBOOK_VERSION,
-            ctx.mdbook_version
-        );
-    }
+    let strict = matches!(
+        ctx.config.get_preprocessor(pre.name()).and_then(|section_config| section_config.get("strict_version_check")),
+        Some(toml::Value::Boolean(true))
+    );
+    check_mdbook_version(pre.name(), mdbook::MDBOOK_VERSION, &ctx.mdbook_version, strict)?;
 
     let processed_book = pre.run(&ctx, book)?;
     serde_json::to_writer(io::stdout(), &processed_book)?;
     Ok(())
 }
 
+pub fn handle_rendering(renderer: &dyn Renderer) -> Result<(), Error> {
+    let ctx = RenderContext::from_json(io::stdin())?;
+
+    let strict = matches!(
+        ctx.config.get(&format!("output.{}.strict_version_check", renderer.name())),
+        Some(toml::Value::Boolean(true))
+    );
+    check_mdbook_version(renderer.name(), mdbook::MDBOOK_VERSION, &ctx.version, strict)?;
+
+    renderer.render(&ctx)
+}
+
+// Logs how far through the book's chapters a search is, every 10 percent, so long builds show they're still going.
+pub(crate) fn log_progress(current: usize, total: usize) {
+    let percent = |chapters: usize| chapters * 100 / total.max(1) / 10 * 10;
+    if current == total || percent(current) != percent(current.saturating_sub(1)) {
+        log::info!("Searched {}% of the chapters ({}/{})", percent(current), current, total);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VersionMismatch {
+    Minor,
+    Major,
+}
+
+// How the version of mdbook running the preprocessor differs from the one it was built against. Any version the
+// built one is caret-compatible with is fine. Under 0.x caret rules a new minor version is a breaking one, so that's a
+// major mismatch, as is a new major version; anything else, like an older patch release, is a minor mismatch.
+// Versions that can't be parsed are a minor mismatch unless they're identical.
+pub fn mdbook_version_mismatch(built_against: &str, running: &str) -> Option<VersionMismatch> {
+    let parse = |version: &str| Version::parse(version.trim().trim_start_matches('v')).ok();
+    let (built, running_version) = match (parse(built_against), parse(running)) {
+        (Some(built), Some(running)) => (built, running),
+        _ if built_against.trim() == running.trim() => return None,
+        _ => return Some(VersionMismatch::Minor),
+    };
+    let compatible = VersionReq::parse(&format!("^{}", built)).is_ok_and(|requirement| requirement.matches(&running_version));
+    let breaking = built.major != running_version.major || (built.major == 0 && built.minor != running_version.minor);
+    match (compatible, breaking) {
+        (true, _) => None,
+        (false, true) => Some(VersionMismatch::Major),
+        (false, false) => Some(VersionMismatch::Minor),
+    }
+}
+
+// Warns about a minor version mismatch and fails on a major one, or on any mismatch when strict.
+fn check_mdbook_version(name: &str, built_against: &str, running: &str, strict: bool) -> Result<(), Error> {
+    let mismatch = match mdbook_version_mismatch(built_against, running) {
+        Some(mismatch) => mismatch,
+        None => return Ok(()),
+    };
+    let message = format!(
+        "The {} preprocessor was built against mdbook {}, but is being run by mdbook {}. \
+         Rebuild it against the mdbook you're using with `cargo install mdbook-git-atom --force`.",
+        name, built_against, running
+    );
+    if mismatch == VersionMismatch::Major || strict {
+        return Err(Error::msg(message));
+    }
+    log::warn!("{} Set strict_version_check = true to fail the build instead.", message);
+    Ok(())
+}
+
+pub fn exit_code(error: &Error) -> i32 {
+    if error.downcast_ref::<ConfigError>().is_some() {
+        2
+    } else if error.downcast_ref::<git2::Error>().is_some() {
+        3
+    } else {
+        1
+    }
+}
+
+pub(crate) fn parse_entry_authors(section_config: &toml::value::Table) -> Result<EntryAuthors, ConfigError> {
+    match section_config.get("entry_authors") {
+        Some(toml::Value::String(entry_authors)) => entry_authors.parse()
+            .map_err(|message| ConfigError::invalid_value("entry_authors", message)),
+        _ => Ok(EntryAuthors::All),
+    }
+}
+
+// Parses a date like 2015-03-01T12:00:00Z. Dates without a time are taken to be midnight UTC.
+pub(crate) fn parse_time(date: &str) -> Option<Time> {
+    DateTime::parse_from_rfc3339(date).map(|date| Time::new(date.timestamp(), date.offset().local_minus_utc() / 60))
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d").map(|date| Time::new(date.and_hms(0, 0, 0).timestamp(), 0)))
+        .ok()
+}
+
+// Returns the blame_since and blame_until options, the dates blame is limited to. Both are optional.
+pub(crate) fn parse_blame_range(section_config: &toml::value::Table) -> Result<(Option<Time>, Option<Time>), ConfigError> {
+    let parse = |key: &str| -> Result<Option<Time>, ConfigError> {
+        let date = match section_config.get(key) {
+            None => return Ok(None),
+            Some(toml::Value::String(date)) => date.to_string(),
+            Some(toml::Value::Datetime(date)) => date.to_string(),
+            Some(value) => return Err(ConfigError::invalid_value(key, format!("{}. Expected a date like 2015-03-01.", value))),
+        };
+        parse_time(&date)
+            .map(Some)
+            .ok_or_else(|| ConfigError::invalid_value(key, format!("{}. Expected a date like 2015-03-01 or 2015-03-01T12:00:00Z.", date)))
+    };
+    let (since, until) = (parse("blame_since")?, parse("blame_until")?);
+    if let (Some(since), Some(until)) = (since, until) {
+        if since > until {
+            return Err(ConfigError::invalid_value("blame_until", format!("{}. Expected a date after blame_since.", section_config["blame_until"])));
+        }
+    }
+    Ok((since, until))
+}
+
+// The options of blaming chapters, which date them and credit their authors, the same for the feed and the recently
+// updated lists.
+pub(crate) fn parse_blame_config(section_config: &toml::value::Table) -> Result<BlameConfig, ConfigError> {
+    let mut blame_config = BlameConfig::default();
+    // first_parent replaces blame_first_parent, which is still read from older configs.
+    let first_parent_key = match (section_config.get("first_parent"), section_config.get("blame_first_parent")) {
+        (Some(_), Some(_)) => {
+            log::warn!("Both first_parent and blame_first_parent are set, using first_parent");
+            "first_parent"
+        }
+        (None, Some(_)) => "blame_first_parent",
+        _ => "first_parent",
+    };
+    match section_config.get(first_parent_key) {
+        None => {}
+        Some(toml::Value::Boolean(first_parent)) => blame_config.first_parent = *first_parent,
+        Some(value) => return Err(ConfigError::invalid_value(first_parent_key, format!("{}. Expected true or false.", value))),
+    }
+    if let Some(toml::Value::Boolean(track_copies)) = section_config.get("blame_track_copies") {
+        blame_config.track_copies = *track_copies;
+    }
+    if let Some(toml::Value::Boolean(follow_renames)) = section_config.get("follow_renames") {
+        blame_config.follow_renames = *follow_renames;
+    }
+    if let Some(toml::Value::String(merge_commit_dates)) = section_config.get("merge_commit_dates") {
+        blame_config.merge_commit_dates = merge_commit_dates.parse()
+            .map_err(|message| ConfigError::invalid_value("merge_commit_dates", message))?;
+    }
+    match section_config.get("max_blame_depth") {
+        None => {}
+        Some(toml::Value::Integer(depth)) => blame_config.max_depth = usize::try_from(*depth)
+            .map_err(|_| ConfigError::invalid_value("max_blame_depth", format!("{}. Expected 0 or a positive number.", depth)))?,
+        Some(value) => return Err(ConfigError::invalid_value("max_blame_depth", format!("{}. Expected a number of commits.", value))),
+    }
+    match section_config.get("blame_timeout_seconds") {
+        None => {}
+        Some(toml::Value::Integer(seconds)) => blame_config.timeout = u64::try_from(*seconds)
+            .map(Duration::from_secs)
+            .map_err(|_| ConfigError::invalid_value("blame_timeout_seconds", format!("{}. Expected 0 or a positive number.", seconds)))?,
+        Some(value) => return Err(ConfigError::invalid_value("blame_timeout_seconds", format!("{}. Expected a number of seconds.", value))),
+    }
+    match section_config.get("max_blame_hunks") {
+        None => {}
+        Some(toml::Value::Integer(hunks)) => blame_config.max_hunks = usize::try_from(*hunks)
+            .map_err(|_| ConfigError::invalid_value("max_blame_hunks", format!("{}. Expected 0 or a positive number.", hunks)))?,
+        Some(value) => return Err(ConfigError::invalid_value("max_blame_hunks", format!("{}. Expected a number of hunks.", value))),
+    }
+    let (blame_since, blame_until) = parse_blame_range(section_config)?;
+    blame_config.since = blame_since;
+    blame_config.until = blame_until;
+    match section_config.get("max_blame_file_bytes") {
+        None => {}
+        Some(toml::Value::Integer(bytes)) => blame_config.max_file_bytes = u64::try_from(*bytes)
+            .map_err(|_| ConfigError::invalid_value("max_blame_file_bytes", format!("{}. Expected 0 or a positive number.", bytes)))?,
+        Some(value) => return Err(ConfigError::invalid_value("max_blame_file_bytes", format!("{}. Expected a number of bytes.", value))),
+    }
+    Ok(blame_config)
+}
+
+pub(crate) const FROM_COMMIT_ENV: &str = "MDBOOK_GIT_ATOM_FROM_COMMIT";
+pub(crate) const TO_COMMIT_ENV: &str = "MDBOOK_GIT_ATOM_TO_COMMIT";
+
+// Returns the commits from the MDBOOK_GIT_ATOM_FROM_COMMIT and MDBOOK_GIT_ATOM_TO_COMMIT environment variables, which
+// CI sets to limit the feed to the changes between two builds. Both or neither have to be set.
+pub(crate) fn parse_commit_range() -> Result<Option<(Oid, Oid)>, ConfigError> {
+    let parse = |name: &str| -> Result<Option<Oid>, ConfigError> {
+        let sha = match std::env::var(name) {
+            Ok(sha) if !sha.trim().is_empty() => sha.trim().to_string(),
+            _ => return Ok(None),
+        };
+        // Shorter shas would be padded with zeroes rather than looked up.
+        match Oid::from_str(&sha) {
+            Ok(oid) if sha.len() == 40 => Ok(Some(oid)),
+            _ => Err(ConfigError::invalid_value(name, format!("{}. Expected a full commit sha.", sha))),
+        }
+    };
+    match (parse(FROM_COMMIT_ENV)?, parse(TO_COMMIT_ENV)?) {
+        (Some(from), Some(to)) => Ok(Some((from, to))),
+        (None, None) => Ok(None),
+        (Some(_), None) => Err(ConfigError::invalid_value(TO_COMMIT_ENV, format!("nothing. Expected a commit sha, since {} is set.", FROM_COMMIT_ENV))),
+        (None, Some(_)) => Err(ConfigError::invalid_value(FROM_COMMIT_ENV, format!("nothing. Expected a commit sha, since {} is set.", TO_COMMIT_ENV))),
+    }
+}
+
+// Returns the max_entry_authors and overflow_author options, which default to crediting every author.
+pub(crate) fn parse_author_limit(section_config: &toml::value::Table) -> Result<AuthorLimit, ConfigError> {
+    let mut author_limit = AuthorLimit::default();
+    match section_config.get("max_entry_authors") {
+        None => {}
+        Some(toml::Value::Integer(max_authors)) => author_limit.max_authors = usize::try_from(*max_authors)
+            .map_err(|_| ConfigError::invalid_value("max_entry_authors", format!("{}. Expected 0 or a positive number.", max_authors)))?,
+        Some(value) => return Err(ConfigError::invalid_value("max_entry_authors", format!("{}. Expected a number of authors.", value))),
+    }
+    match section_config.get("overflow_author") {
+        None | Some(toml::Value::Boolean(true)) => {}
+        Some(toml::Value::Boolean(false)) => author_limit.overflow_author = None,
+        Some(toml::Value::String(name)) => author_limit.overflow_author = Some(name.to_string()),
+        Some(value) => return Err(ConfigError::invalid_value("overflow_author", format!("{}. Expected a name like \"and {{count}} others\", or false.", value))),
+    }
+    Ok(author_limit)
+}
+
+// Returns the words_per_minute and count_code_blocks options used for reading times.
+pub(crate) fn parse_reading_speed(section_config: &toml::value::Table) -> Result<(u32, bool), ConfigError> {
+    let mut words_per_minute = 200;
+    if let Some(toml::Value::Integer(configured_words_per_minute)) = section_config.get("words_per_minute") {
+        if *configured_words_per_minute < 1 || *configured_words_per_minute > u32::MAX as i64 {
+            return Err(ConfigError::invalid_value("words_per_minute", format!("{}. Expected a positive number.", configured_words_per_minute)));
+        }
+        words_per_minute = *configured_words_per_minute as u32;
+    }
+    let mut count_code_blocks = true;
+    if let Some(toml::Value::Boolean(configured_count_code_blocks)) = section_config.get("count_code_blocks") {
+        count_code_blocks = *configured_count_code_blocks;
+    }
+    Ok((words_per_minute, count_code_blocks))
+}
+
+// Returns the sort_by and sort_direction options, defaulting to the most recently modified posts first.
+pub(crate) fn parse_sort(section_config: &toml::value::Table) -> Result<(SortField, SortDirection), ConfigError> {
+    let mut sort_field = SortField::LastModified;
+    if let Some(toml::Value::String(configured_sort_field)) = section_config.get("sort_by") {
+        sort_field = configured_sort_field.parse()
+            .map_err(|message| ConfigError::invalid_value("sort_by", message))?;
+    }
+    let mut sort_direction = SortDirection::Descending;
+    if let Some(toml::Value::String(configured_sort_direction)) = section_config.get("sort_direction") {
+        sort_direction = configured_sort_direction.parse()
+            .map_err(|message| ConfigError::invalid_value("sort_direction", message))?;
+    }
+    Ok((sort_field, sort_direction))
+}
+
+// Returns the invalid_utf8 option, defaulting to replacing invalid bytes.
+pub(crate) fn parse_invalid_utf8(section_config: &toml::value::Table) -> Result<InvalidUtf8, ConfigError> {
+    match section_config.get("invalid_utf8") {
+        Some(toml::Value::String(invalid_utf8)) => invalid_utf8.parse()
+            .map_err(|message| ConfigError::invalid_value("invalid_utf8", message)),
+        _ => Ok(InvalidUtf8::Lossy),
+    }
+}
+
+// Returns the paths in the exclude option, along with the default excluded paths unless exclude_defaults is false.
+pub(crate) fn parse_excluded_paths(section_config: &toml::value::Table) -> Result<Vec<PathBuf>, ConfigError> {
+    let mut excluded_paths: Vec<PathBuf> = vec![];
+    if !matches!(section_config.get("exclude_defaults"), Some(toml::Value::Boolean(false))) {
+        excluded_paths.extend(DEFAULT_EXCLUDED_PATHS.iter().map(PathBuf::from));
+    }
+    if let Some(exclude) = section_config.get("exclude") {
+        let paths: Vec<PathBuf> = exclude.as_array()
+            .and_then(|paths| paths.iter().map(|path| path.as_str().map(PathBuf::from)).collect())
+            .ok_or_else(|| ConfigError::invalid_value("exclude", format!("{}. Expected a list of paths.", exclude)))?;
+        excluded_paths.extend(paths);
+    }
+    Ok(excluded_paths)
+}
+
+// Returns the retry_open_attempts option, defaulting to trying once.
+pub(crate) fn parse_retry_open_attempts(section_config: &toml::value::Table) -> Result<u32, ConfigError> {
+    match section_config.get("retry_open_attempts") {
+        Some(toml::Value::Integer(attempts)) => u32::try_from(*attempts).ok()
+            .filter(|attempts| *attempts > 0)
+            .ok_or_else(|| ConfigError::invalid_value("retry_open_attempts", format!("{}. Expected a positive number.", attempts))),
+        _ => Ok(1),
+    }
+}
+
+// Returns the section option, which limits lists and feeds to chapters in that part of the book.
+pub(crate) fn parse_section(section_config: &toml::value::Table) -> Option<String> {
+    match section_config.get("section") {
+        Some(toml::Value::String(section)) => Some(section.to_string()),
+        _ => None,
+    }
+}
+
+// Returns the remote_fallback option, like { provider = "github", repo = "me/book", token_env = "GITHUB_TOKEN" }.
+pub(crate) fn parse_remote_fallback(section_config: &toml::value::Table) -> Result<Option<RemoteFallback>, ConfigError> {
+    let remote_fallback = match section_config.get("remote_fallback") {
+        None => return Ok(None),
+        Some(toml::Value::Table(remote_fallback)) => remote_fallback,
+        Some(value) => return Err(ConfigError::invalid_value("remote_fallback", format!("{}. Expected a table, like {{ provider = \"github\", repo = \"me/book\" }}.", value))),
+    };
+    let provider = match remote_fallback.get("provider") {
+        Some(toml::Value::String(provider)) => provider.parse()
+            .map_err(|message| ConfigError::invalid_value("remote_fallback.provider", message))?,
+        _ => return Err(ConfigError::invalid_value("remote_fallback.provider", "missing. Expected \"github\".".to_string())),
+    };
+    let repo = match remote_fallback.get("repo") {
+        Some(toml::Value::String(repo)) if repo.contains('/') => repo.to_string(),
+        value => return Err(ConfigError::invalid_value("remote_fallback.repo", format!("{}. Expected a repository like \"me/book\".", value.map_or("missing".to_string(), |value| value.to_string())))),
+    };
+    let token_env = match remote_fallback.get("token_env") {
+        None => None,
+        Some(toml::Value::String(token_env)) => Some(token_env.to_string()),
+        Some(value) => return Err(ConfigError::invalid_value("remote_fallback.token_env", format!("{}. Expected the name of an environment variable.", value))),
+    };
+    Ok(Some(RemoteFallback { provider, repo, token_env }))
+}
+
+// Reads the dates_file option's file, relative to root_path and defaulting to dates.toml, which maps chapter paths to
+// the dates that override the ones from git, like "imported/page.md" = { published = 2015-03-01, updated = 2016-07-12T10:00:00Z }.
+// The file is optional.
+pub(crate) fn parse_date_overrides(section_config: &toml::value::Table, root_path: &Path) -> Result<HashMap<PathBuf, DateOverride>, ConfigError> {
+    let dates_file = match section_config.get("dates_file") {
+        None => "dates.toml",
+        Some(toml::Value::String(dates_file)) => dates_file.as_str(),
+        Some(value) => return Err(ConfigError::invalid_value("dates_file", format!("{}. Expected a path.", value))),
+    };
+    let path = root_path.join(dates_file);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            if section_config.contains_key("dates_file") {
+                log::warn!("{} doesn't exist, using dates from git", path.display());
+            }
+            return Ok(HashMap::new());
+        }
+        Err(error) => return Err(ConfigError::invalid_value("dates_file", format!("{}. Unable to read it: {}", dates_file, error))),
+    };
+    let dates: toml::value::Table = toml::from_str(&contents)
+        .map_err(|error| ConfigError::invalid_value("dates_file", format!("{}. {}", dates_file, error)))?;
+
+    let parse_date = |chapter: &str, field: &str, value: Option<&toml::Value>| -> Result<Option<Time>, ConfigError> {
+        let date = match value {
+            None => return Ok(None),
+            Some(toml::Value::String(date)) => date.to_string(),
+            Some(toml::Value::Datetime(date)) => date.to_string(),
+            Some(value) => return Err(ConfigError::invalid_value("dates_file", format!("{}. The {} date of {} is {}, expected a date.", dates_file, field, chapter, value))),
+        };
+        parse_time(&date)
+            .map(Some)
+            .ok_or_else(|| ConfigError::invalid_value("dates_file", format!("{}. The {} date of {} is \"{}\", expected a date like 2015-03-01T12:00:00Z.", dates_file, field, chapter, date)))
+    };
+    dates.iter()
+        .map(|(chapter, dates)| {
+            let dates = dates.as_table()
+                .ok_or_else(|| ConfigError::invalid_value("dates_file", format!("{}. Expected {} to have a table of dates, like {{ published = 2015-03-01 }}.", dates_file, chapter)))?;
+            let date_override = DateOverride {
+                published: parse_date(chapter, "published", dates.get("published"))?,
+                updated: parse_date(chapter, "updated", dates.get("updated"))?,
+            };
+            Ok((PathBuf::from(chapter), date_override))
+        })
+        .collect()
+}
+
+// Returns the translations option, the languages whose chapters are in top-level directories of the same name.
+pub(crate) fn parse_translations(section_config: &toml::value::Table) -> Result<Vec<String>, ConfigError> {
+    match section_config.get("translations") {
+        None => Ok(vec![]),
+        Some(toml::Value::Array(languages)) => languages.iter()
+            .map(|language| match language {
+                toml::Value::String(language) if !language.is_empty() && !language.contains(['/', '\\']) => Ok(language.to_string()),
+                _ => Err(ConfigError::invalid_value("translations", format!("{}. Expected directory names, like \"en\".", language))),
+            })
+            .collect(),
+        Some(value) => Err(ConfigError::invalid_value("translations", format!("{}. Expected a list of languages, like [\"en\", \"de\"].", value))),
+    }
+}
+
+// Returns the renderers option, the renderers the preprocessor runs for. Defaults to just html.
+pub(crate) fn parse_renderers(section_config: &toml::value::Table) -> Result<Vec<String>, ConfigError> {
+    match section_config.get("renderers") {
+        None => Ok(vec!["html".to_string()]),
+        Some(toml::Value::Array(renderers)) => renderers.iter()
+            .map(|renderer| match renderer {
+                toml::Value::String(renderer) if !renderer.is_empty() => Ok(renderer.to_string()),
+                _ => Err(ConfigError::invalid_value("renderers", format!("{}. Expected renderer names, like \"html\".", renderer))),
+            })
+            .collect(),
+        Some(value) => Err(ConfigError::invalid_value("renderers", format!("{}. Expected a list of renderers, like [\"html\", \"markdown\"].", value))),
+    }
+}
+
+// Returns the url for the icon or logo option, which is either an absolute url or a path in the src directory.
+// mdbook copies files in src to the book, so paths become urls under base_url.
+pub(crate) fn parse_feed_image(section_config: &toml::value::Table, key: &str, src_path: &Path, base_url: &Url, on_missing_asset: OnMissingAsset) -> Result<Option<String>, ConfigError> {
+    let value = match section_config.get(key) {
+        None => return Ok(None),
+        Some(toml::Value::String(value)) => value,
+        Some(value) => return Err(ConfigError::invalid_value(key, format!("{}. Expected a url or a path in the src directory.", value))),
+    };
+    if let Ok(url) = Url::parse(value) {
+        return Ok(Some(url.to_string()));
+    }
+    let path = Path::new(value);
+    if !path.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir)) {
+        return Err(ConfigError::invalid_value(key, format!("{}. Paths have to be in the src directory, which mdbook copies to the book.", value)));
+    }
+    if !src_path.join(path).is_file() {
+        let message = format!("{} doesn't exist in {}", value, src_path.display());
+        match on_missing_asset {
+            OnMissingAsset::Fail => return Err(ConfigError::invalid_value(key, message)),
+            OnMissingAsset::Warn => log::warn!("The feed's {} {}", key, message),
+        }
+    }
+    let relative_url = value.replace('\\', "/");
+    base_url.join(relative_url.trim_start_matches("./"))
+        .map(|url| Some(url.to_string()))
+        .map_err(|error| ConfigError::invalid_value(key, format!("{}. {}", value, error)))
+}
+
+// The file in the book root the atom preprocessor leaves the feed's url in, for the updated preprocessor and any other
+// tools that want to link to the feed without configuring its url again.
+pub(crate) const FEED_ENV_FILE: &str = ".mdbook-git-atom.env";
+
+#[derive(Serialize, Deserialize)]
+struct FeedEnv {
+    atom_feed_url: String,
+}
+
+pub(crate) fn write_feed_env(root: &Path, feed_url: &Url) -> io::Result<()> {
+    let feed_env = FeedEnv { atom_feed_url: feed_url.to_string() };
+    write_if_changed(&root.join(FEED_ENV_FILE), &serde_json::to_string(&feed_env).expect("Serialize the feed env file"))
+        .map(|_| ())
+}
+
+// The feed url in the book's env file, if the atom preprocessor has written one.
+pub(crate) fn read_feed_env(root: &Path) -> Option<String> {
+    let contents = fs::read_to_string(root.join(FEED_ENV_FILE)).ok()?;
+    match serde_json::from_str::<FeedEnv>(&contents) {
+        Ok(feed_env) => Some(feed_env.atom_feed_url),
+        Err(error) => {
+            log::warn!("Ignoring {}, which couldn't be read: {}", FEED_ENV_FILE, error);
+            None
+        }
+    }
+}
+
+// Writes contents to path unless it already has them. Feeds are written into the book's src directory, and a
+// changed modification time makes `mdbook serve` rebuild the book, which would write the feed again, forever.
+// Returns whether the file was written.
+pub fn write_if_changed(path: &Path, contents: &str) -> io::Result<bool> {
+    if fs::read_to_string(path).map(|existing| existing == contents).unwrap_or(false) {
+        log::debug!("{} is unchanged, not writing it", path.display());
+        return Ok(false);
+    }
+    // Written to a hidden file next to path and renamed over it, so servers and file watchers never see a partial file.
+    let file_name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{} isn't a file name", path.display())))?;
+    let mut temporary_name = OsString::from(".");
+    temporary_name.push(file_name);
+    temporary_name.push(".tmp");
+    let temporary_path = path.with_file_name(temporary_name);
+    fs::write(&temporary_path, contents)?;
+    if let Err(error) = fs::rename(&temporary_path, path) {
+        let _ = fs::remove_file(&temporary_path);
+        return Err(error);
+    }
+    Ok(true)
+}
+
+// Checks the feed given to the check subcommand, printing any problems. Exits with 0 if the feed is valid, 1 if there
+// are only warnings and 2 if there are errors.
+pub fn handle_check(sub_args: &ArgMatches) -> ! {
+    let path = sub_args.value_of("feed").expect("Required argument");
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) => {
+            eprintln!("{}: error: Unable to read the feed: {}", path, error);
+            process::exit(2);
+        }
+    };
+
+    let violations = check_feed(&contents, sub_args.is_present("strict"));
+    for violation in violations.iter() {
+        match violation.line {
+            Some(line) => eprintln!("{}:{}: {}: {}", path, line, violation.severity, violation.message),
+            None => eprintln!("{}: {}: {}", path, violation.severity, violation.message),
+        }
+    }
+    match violations.iter().map(|violation| violation.severity).max() {
+        None => process::exit(0),
+        Some(Severity::Warning) => process::exit(1),
+        Some(Severity::Error) => process::exit(2),
+    }
+}
+
 pub fn handle_supports(pre: &dyn Preprocessor, sub_args: &ArgMatches) -> ! {
     let renderer = sub_args.value_of("renderer").expect("Required argument");
-    let supported = pre.supports_renderer(&renderer);
+    let supported = pre.supports_renderer(renderer);
     if supported {
         process::exit(0);
     } else {
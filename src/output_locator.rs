@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use mdbook::Config;
+use mdbook::preprocess::PreprocessorContext;
+
+// Which directory a generated file is written to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Destination {
+    // The book's src directory, which mdbook copies into the html output along with the chapters.
+    Src,
+    // The html renderer's output directory, so the src directory isn't touched.
+    Build,
+}
+
+impl FromStr for Destination {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "src" => Ok(Destination::Src),
+            "build" => Ok(Destination::Build),
+            _ => Err(format!("{}. Expected \"src\" or \"build\".", value)),
+        }
+    }
+}
+
+// Finds the directories generated files are written to, from the book's config. Books with a single renderer have its
+// output in the build directory, and books with several have each renderer's output in a directory of its own, like
+// book/html.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputLocator {
+    src_dir: PathBuf,
+    build_dir: PathBuf,
+    html_dir: PathBuf,
+}
+
+impl OutputLocator {
+    pub fn new(root: &Path, config: &Config) -> OutputLocator {
+        let build_dir = root.join(&config.build.build_dir);
+        // mdbook renders with the html renderer when there are no [output] tables.
+        let renderers = config.get("output").and_then(toml::Value::as_table).map_or(0, |outputs| outputs.len());
+        let html_dir = if renderers <= 1 { build_dir.clone() } else { build_dir.join("html") };
+        OutputLocator {
+            src_dir: root.join(&config.book.src),
+            build_dir,
+            html_dir,
+        }
+    }
+
+    pub fn from_context(ctx: &PreprocessorContext) -> OutputLocator {
+        OutputLocator::new(&ctx.root, &ctx.config)
+    }
+
+    // Writes every file to directory, like a renderer writing to its own directory.
+    pub fn in_directory(directory: impl Into<PathBuf>) -> OutputLocator {
+        let directory = directory.into();
+        OutputLocator {
+            src_dir: directory.clone(),
+            build_dir: directory.clone(),
+            html_dir: directory,
+        }
+    }
+
+    pub fn src_dir(&self) -> &Path {
+        &self.src_dir
+    }
+
+    pub fn build_dir(&self) -> &Path {
+        &self.build_dir
+    }
+
+    pub fn html_dir(&self) -> &Path {
+        &self.html_dir
+    }
+
+    pub fn directory(&self, destination: Destination) -> &Path {
+        match destination {
+            Destination::Src => &self.src_dir,
+            Destination::Build => &self.html_dir,
+        }
+    }
+
+    // Where the file at path, relative to the destination's directory, is written.
+    pub fn locate(&self, destination: Destination, path: impl AsRef<Path>) -> PathBuf {
+        self.directory(destination).join(path)
+    }
+}
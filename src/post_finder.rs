@@ -1,183 +1,2407 @@
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
-use std::path::PathBuf;
-use git2::{Blame, BlameOptions, Repository, Time};
-use mdbook::book::Book;
+use std::borrow::Cow;
+use std::cell::{Cell, OnceCell};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use chrono::{DateTime, Utc};
+use git2::{Blame, BlameHunk, BlameOptions, Delta, DiffFindOptions, DiffOptions, ErrorCode, Oid, Repository, Sort, Time};
+use mdbook::book::{Book, Chapter};
 use mdbook::BookItem;
-use pulldown_cmark::{html, Options, Parser};
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use url::Url;
+use crate::errors::{PostError, SearchError};
+use crate::git_utils::{find_submodule_repo, note_date, shallow_boundary};
+use crate::library_helpers::parse_time;
+use crate::remote_history::{PathHistory, RemoteHistory};
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Author {
     pub(crate) name: String,
     pub(crate) email: Option<String>
 }
 
+impl Author {
+    pub fn new(name: impl Into<String>, email: Option<String>) -> Author {
+        Author { name: name.into(), email }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Post {
     pub(crate) path: PathBuf,
+    #[serde(with = "time_serde")]
     pub(crate) last_modified_date: Time,
+    #[serde(with = "time_serde")]
     pub(crate) created_date: Time,
-    pub(crate) authors: HashSet<Author>,
+    // Every author of the post, with the number of lines they own according to git blame.
+    #[serde(with = "authors_serde")]
+    pub(crate) authors: HashMap<Author, usize>,
     pub(crate) title: String,
     pub(crate) id: String,
     pub(crate) content: Option<String>,
+    // Number of words in the whole chapter, even when only a preview is rendered.
+    pub(crate) word_count: Option<u32>,
+    // Estimated minutes it takes to read the whole chapter.
+    pub(crate) reading_time: Option<u32>,
+    // Source of the first image in the chapter, as written in the markdown. Data URIs and SVGs are ignored.
+    pub(crate) image: Option<String>,
+    // The part of the book this post is in, or its top-level directory when the book has no parts.
+    pub(crate) section: Option<String>,
+    // Names of the chapters this post is nested under, outermost first.
+    pub(crate) ancestors: Vec<String>,
+    // Headings of the parts of the chapter changed by its latest commit, when changed sections are enabled.
+    pub(crate) changed_sections: Vec<String>,
+    // Number of blame hunks, each a block of lines from a single commit. Chapters that are revised often have more.
+    pub(crate) churn: u32,
+    // The license of the post, from the rights key of its front matter or the book's default.
+    pub(crate) rights: Option<String>,
+    // Whether the post is dated by its git history, rather than by the filesystem or HEAD, like uncommitted and
+    // synthesized chapters are.
+    #[serde(default)]
+    pub(crate) is_git_tracked: bool,
+    // The chapter's file, relative to the root of the repository it's blamed in.
+    #[serde(default)]
+    pub(crate) source_path: Option<PathBuf>,
+    // The full shas of the commits that created the chapter and last changed it, when its history has them.
+    #[serde(default)]
+    pub(crate) created_commit: Option<String>,
+    #[serde(default)]
+    pub(crate) last_commit: Option<String>,
+    // How many commits changed the chapter, which is 0 when its history isn't known.
+    #[serde(default)]
+    pub(crate) revision_count: u32,
+}
+
+// A block of lines in a file that were last changed by the same commit.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlameHunkInfo {
+    // The first line of the hunk, counting from 1.
+    pub start_line: usize,
+    pub num_lines: usize,
+    pub commit_id: String,
+    pub author_name: String,
+    pub author_email: Option<String>,
+    #[serde(with = "time_serde")]
+    pub date: Time,
+}
+
+// Aggregate numbers about a book and its repository.
+pub struct BookStatistics {
+    pub total_chapters: usize,
+    // Number of distinct authors across every chapter.
+    pub total_authors: usize,
+    // Number of commits reachable from HEAD, including ones that didn't touch the book.
+    pub total_commits: usize,
+    pub first_commit: Option<Time>,
+    pub latest_commit: Option<Time>,
+}
+
+// What an author contributed to a book, from the commits that changed its chapters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuthorStats {
+    // Number of chapters the author committed to.
+    pub chapters_authored: usize,
+    // Number of chapters whose latest commit is the author's.
+    pub chapters_last_modified: usize,
+    pub first_contribution: Time,
+    pub latest_contribution: Time,
+}
+
+// Which of a post's authors to credit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryAuthors {
+    All,
+    // The author owning the most lines.
+    Primary,
+    // The authors owning the most lines, at most this many.
+    Top(usize),
+}
+
+// Caps how many of the authors entry_authors picks are credited, so pages touched by many people don't list all of them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthorLimit {
+    // The most authors credited on each post. 0 credits every author.
+    pub max_authors: usize,
+    // The name of the author standing in for the ones left out, with {count} for how many there are. None leaves
+    // them out without saying so.
+    pub overflow_author: Option<String>,
+}
+
+impl Default for AuthorLimit {
+    fn default() -> AuthorLimit {
+        AuthorLimit {
+            max_authors: 0,
+            overflow_author: Some("and {count} others".to_string()),
+        }
+    }
+}
+
+impl FromStr for EntryAuthors {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<EntryAuthors, String> {
+        match value {
+            "all" => Ok(EntryAuthors::All),
+            "primary" => Ok(EntryAuthors::Primary),
+            _ => value.strip_prefix("top:")
+                .and_then(|count| count.parse::<usize>().ok())
+                .filter(|count| *count > 0)
+                .map(EntryAuthors::Top)
+                .ok_or_else(|| format!("{}. Expected \"all\", \"primary\" or \"top:N\".", value)),
+        }
+    }
+}
+
+// How chapters that aren't valid UTF-8 are read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidUtf8 {
+    // Invalid bytes are replaced with U+FFFD.
+    Lossy,
+    // The chapter is still a post, with its dates and authors, but without any content.
+    Skip,
+    Error,
+}
+
+impl FromStr for InvalidUtf8 {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<InvalidUtf8, String> {
+        match value {
+            "lossy" => Ok(InvalidUtf8::Lossy),
+            "skip" => Ok(InvalidUtf8::Skip),
+            "error" => Ok(InvalidUtf8::Error),
+            _ => Err(format!("{}. Expected \"lossy\", \"skip\" or \"error\".", value)),
+        }
+    }
+}
+
+// Whether merge commits can date the chapters they changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeCommitDates {
+    Use,
+    // Lines blamed on a merge are dated by the newest commit it merged that changed the chapter, like the commit on a
+    // long-lived branch that actually changed them.
+    Skip,
+}
+
+impl FromStr for MergeCommitDates {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<MergeCommitDates, String> {
+        match value {
+            "use" => Ok(MergeCommitDates::Use),
+            "skip" => Ok(MergeCommitDates::Skip),
+            _ => Err(format!("{}. Expected \"use\" or \"skip\".", value)),
+        }
+    }
+}
+
+// What search sorts posts by.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortField {
+    LastModified,
+    Created,
+    Title,
+}
+
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+impl FromStr for SortField {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<SortField, String> {
+        match value {
+            "last_modified" => Ok(SortField::LastModified),
+            "created" => Ok(SortField::Created),
+            "title" => Ok(SortField::Title),
+            _ => Err(format!("{}. Expected \"last_modified\", \"created\" or \"title\".", value)),
+        }
+    }
+}
+
+impl FromStr for SortDirection {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<SortDirection, String> {
+        match value {
+            "ascending" => Ok(SortDirection::Ascending),
+            "descending" => Ok(SortDirection::Descending),
+            _ => Err(format!("{}. Expected \"ascending\" or \"descending\".", value)),
+        }
+    }
+}
+
+// Dates that override the ones from git for a chapter, like for content imported from elsewhere.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DateOverride {
+    pub published: Option<Time>,
+    pub updated: Option<Time>,
 }
 
+// How chapters are blamed.
+#[derive(Clone, Copy, Debug)]
+pub struct BlameConfig {
+    // Whether only the first parent of merge commits is followed, so commits on merged branches are credited to the merge.
+    pub first_parent: bool,
+    // Whether lines moved or copied from other files in the same commit keep their original authors.
+    pub track_copies: bool,
+    // How many commits back from HEAD blame looks. Lines older than that are blamed on the commit at that depth, which
+    // keeps blame quick in repositories with long histories. 0 looks through the whole history.
+    pub max_depth: usize,
+    // Blames taking longer than this are logged, since git can't stop a blame partway through.
+    pub timeout: Duration,
+    // How many blame hunks of each chapter are credited to their authors, which bounds the memory used by chapters
+    // with thousands of edits. Dates still come from the whole blame. 0 credits every hunk.
+    pub max_hunks: usize,
+    // Only lines last changed from this date on are dated and credited, like for a feed of the last month's changes.
+    // Chapters without any are left out.
+    pub since: Option<Time>,
+    // Chapters are blamed as they were at this date, leaving out later changes.
+    pub until: Option<Time>,
+    // Chapters larger than this many bytes, like ones with embedded images, aren't blamed, since blaming them can take
+    // minutes. They're dated and credited by the commits that changed them instead. 0 blames chapters of any size.
+    pub max_file_bytes: u64,
+    // Whether merge commits date the lines blamed on them, and the chapters changed by them in log-based histories.
+    pub merge_commit_dates: MergeCommitDates,
+    // Whether log-based histories, like revision counts, follow chapters across renames. Off by default, since finding
+    // renames compares the contents of every added and deleted file.
+    pub follow_renames: bool,
+}
+
+impl Default for BlameConfig {
+    fn default() -> BlameConfig {
+        BlameConfig {
+            first_parent: true,
+            track_copies: true,
+            max_depth: 10_000,
+            timeout: Duration::from_secs(30),
+            max_hunks: 10_000,
+            since: None,
+            until: None,
+            max_file_bytes: 1_000_000,
+            merge_commit_dates: MergeCommitDates::Use,
+            follow_renames: false,
+        }
+    }
+}
+
+// Pages mdbook renders at the root of every book.
+const RESERVED_PAGES: [&str; 2] = ["print.html", "404.html"];
+
+// Files that are part of the book's structure rather than its content.
+pub const DEFAULT_EXCLUDED_PATHS: [&str; 1] = ["SUMMARY.md"];
+
 pub struct PostFinder {
-    repo: Repository
+    repository_path: PathBuf,
+    // Opened when it's first needed, since the repository might not be available yet when the PostFinder is made.
+    repo: OnceCell<Repository>,
+    // The oldest commit blame looks at, when the history is deeper than blame_config.max_depth.
+    depth_boundary: OnceCell<Option<Oid>>,
+    // The newest commit at blame_config.until and the newest commit before blame_config.since.
+    date_boundaries: OnceCell<(Option<Oid>, Option<Oid>)>,
+    // The history of every path in the repository, from a single walk of the commits blame looks at.
+    path_histories: OnceCell<HashMap<PathBuf, PathHistory>>,
+    // The book's src directory, relative to the root of the repository. Once a search sets it, the walk of the
+    // repository's history only diffs the paths in it.
+    history_pathspec: OnceCell<PathBuf>,
+    // How many times opening the repository is tried before giving up.
+    retry_open_attempts: u32,
+    // Chapters at these paths, relative to the book's src directory, never become posts.
+    excluded_paths: Vec<PathBuf>,
+    blame_config: BlameConfig,
+    // Whether chapters with content but no source file (usually created by other preprocessors) become posts.
+    include_synthesized_chapters: bool,
+    // Chapters nested deeper than this are rolled up into their ancestor at this depth. 0 disables rolling up.
+    rollup_depth: usize,
+    // Reading speed used to estimate reading times.
+    words_per_minute: u32,
+    // Whether words in code blocks count towards word counts and reading times.
+    count_code_blocks: bool,
+    // What to do with chapters that aren't valid UTF-8.
+    invalid_utf8: InvalidUtf8,
+    // Whether posts list the sections changed by their latest commit.
+    changed_sections: bool,
+    // Where to look up the history of chapters older than a shallow clone.
+    remote_history: Option<RemoteHistory>,
+    // Notes in this ref on the commit that created a chapter override its created date.
+    notes_ref: Option<String>,
+    // Posts that haven't been modified for longer than this are left out of searches.
+    max_age: Option<Duration>,
+    // Dates for chapters, by their path relative to the src directory, that override the ones from git.
+    date_overrides: HashMap<PathBuf, DateOverride>,
+    // Whether book statistics count the commits on every branch, instead of only the ones reachable from HEAD.
+    all_branches: bool,
+    // How many posts searches for a limited number of entries hold beyond that number before dropping the oldest.
+    search_chunk_size: usize,
+    // Whether the first "# " heading is left out of rendered content, since it usually repeats the title.
+    strip_h1_from_content: bool,
+    // The license of posts that don't set rights in their front matter.
+    entry_rights: Option<String>,
+    // Lines of rendered content longer than this many bytes are cut short. 0 keeps lines of any length.
+    max_preview_line_bytes: usize,
+    // Blame only looks at the commits after the first and up to the second, and chapters that weren't changed by any
+    // of them are left out. Set for the length of a search by search_in_range.
+    commit_range: Cell<Option<(Oid, Oid)>>,
+}
+
+// Why a chapter of the book isn't a post.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    // Listed in SUMMARY.md without a file.
+    Draft,
+    Excluded,
+    // Its file couldn't be dated, usually because it doesn't exist.
+    NoHistory,
+    // Made by another preprocessor without a source file, when those aren't posts or it has no content.
+    Synthesized,
+}
+
+// Why a chapter has no post: it was left out, or it couldn't be read.
+enum ChapterError {
+    Skipped(SkipReason),
+    Failed(PostError),
 }
 
 impl PostFinder {
-    pub fn new(repository_path: &str) -> PostFinder {
-        let repo = match Repository::open(repository_path) {
-            Ok(repo) => repo,
-            Err(e) => panic!("failed to open: {}", e),
+    // Opens the repository right away, failing if it can't be.
+    pub fn try_new(repository_path: &str) -> Result<PostFinder, git2::Error> {
+        let post_finder = PostFinder::new(repository_path);
+        post_finder.open()?;
+        Ok(post_finder)
+    }
+
+    // Doesn't open the repository until it's first needed.
+    pub fn new(repository_path: impl Into<PathBuf>) -> PostFinder {
+        PostFinder {
+            repository_path: repository_path.into(),
+            repo: OnceCell::new(),
+            depth_boundary: OnceCell::new(),
+            date_boundaries: OnceCell::new(),
+            path_histories: OnceCell::new(),
+            history_pathspec: OnceCell::new(),
+            retry_open_attempts: 1,
+            blame_config: BlameConfig::default(),
+            excluded_paths: DEFAULT_EXCLUDED_PATHS.iter().map(PathBuf::from).collect(),
+            include_synthesized_chapters: true,
+            rollup_depth: 0,
+            words_per_minute: 200,
+            count_code_blocks: true,
+            strip_h1_from_content: true,
+            invalid_utf8: InvalidUtf8::Lossy,
+            changed_sections: false,
+            remote_history: None,
+            notes_ref: None,
+            max_age: None,
+            date_overrides: HashMap::new(),
+            all_branches: false,
+            search_chunk_size: 50,
+            entry_rights: None,
+            max_preview_line_bytes: 10_000,
+            commit_range: Cell::new(None),
+        }
+    }
+
+    // The url mdbook renders the chapter at path, relative to the src directory, to. The url is relative when there's
+    // no base_url.
+    pub fn compute_url(base_url: Option<&Url>, path: &Path) -> Option<String> {
+        let url_string = match base_url {
+            Some(base_url) => base_url.join(path.to_str()?).ok()?.to_string(),
+            None => path.to_str()?.to_string(),
         };
 
-        PostFinder { repo }
+        let readme_regex = Regex::new(r"README.md$").unwrap();
+        let url_string = readme_regex.replace_all(url_string.as_str(), "index.html").to_string();
+        let md_regex = Regex::new(r"md$").unwrap();
+        Some(md_regex.replace_all(url_string.as_str(), "html").to_string())
     }
 
-    pub fn search(&self, book: &Book, content_path: &PathBuf, max_number_of_lines: Option<i64>, target_entries: i64) -> Vec<Post> {
-        let mut posts: Vec<Post> = book
-            .iter()
-            .filter_map({ |item|
-                if let BookItem::Chapter(chapter) = item {
-                    let path = content_path.join(chapter.source_path.as_ref()?.as_path());
-                    self.post(path, chapter.name.to_string(), chapter.path.as_ref()?.to_path_buf(), max_number_of_lines)
-                } else {
-                    None
+    // Opens the repository if it isn't already, retrying with a backoff up to retry_open_attempts times.
+    pub fn open(&self) -> Result<(), git2::Error> {
+        self.repo().map(|_| ())
+    }
+
+    fn repo(&self) -> Result<&Repository, git2::Error> {
+        if let Some(repo) = self.repo.get() {
+            return Ok(repo);
+        }
+        let mut attempt = 1;
+        let repo = loop {
+            // Books in a subdirectory of a repository, like a monorepo's docs, are found by looking up from the book.
+            match Repository::discover(&self.repository_path) {
+                Ok(repo) => break repo,
+                Err(error) if attempt >= self.retry_open_attempts => return Err(error),
+                Err(error) => {
+                    let backoff = Duration::from_millis(250 * 2u64.pow(attempt - 1));
+                    log::warn!("Unable to open {} ({}), retrying in {:?}", self.repository_path.display(), error.message(), backoff);
+                    thread::sleep(backoff);
+                    attempt += 1;
                 }
+            }
+        };
+        Ok(self.repo.get_or_init(|| repo))
+    }
+
+    pub fn with_retry_open_attempts(mut self, retry_open_attempts: u32) -> PostFinder {
+        self.retry_open_attempts = retry_open_attempts.max(1);
+        self
+    }
+
+    // Whether the repository has any commits at all.
+    pub fn has_history(&self) -> bool {
+        self.repo().map(|repo| !repo.is_empty().unwrap_or(true)).unwrap_or(false)
+    }
+
+    // When the commit HEAD points to was made.
+    pub fn head_time(&self) -> Option<Time> {
+        Some(self.repo().ok()?.head().ok()?.peel_to_commit().ok()?.time())
+    }
+
+    pub fn with_blame_config(mut self, blame_config: BlameConfig) -> PostFinder {
+        self.blame_config = blame_config;
+        self
+    }
+
+    // Replaces the default excluded paths, DEFAULT_EXCLUDED_PATHS.
+    pub fn with_excluded_paths(mut self, excluded_paths: Vec<PathBuf>) -> PostFinder {
+        self.excluded_paths = excluded_paths;
+        self
+    }
+
+    pub fn with_rollup_depth(mut self, rollup_depth: usize) -> PostFinder {
+        self.rollup_depth = rollup_depth;
+        self
+    }
+
+    pub fn with_reading_speed(mut self, words_per_minute: u32, count_code_blocks: bool) -> PostFinder {
+        self.words_per_minute = words_per_minute.max(1);
+        self.count_code_blocks = count_code_blocks;
+        self
+    }
+
+    pub fn with_strip_h1_from_content(mut self, strip_h1_from_content: bool) -> PostFinder {
+        self.strip_h1_from_content = strip_h1_from_content;
+        self
+    }
+
+    pub fn with_max_preview_line_bytes(mut self, max_preview_line_bytes: usize) -> PostFinder {
+        self.max_preview_line_bytes = max_preview_line_bytes;
+        self
+    }
+
+    // Limits every search to the chapters changed after the first commit and up to the second, like search_in_range.
+    pub fn with_blame_commit_range(self, commit_range: Option<(Oid, Oid)>) -> PostFinder {
+        self.commit_range.set(commit_range);
+        self
+    }
+
+    pub fn with_invalid_utf8(mut self, invalid_utf8: InvalidUtf8) -> PostFinder {
+        self.invalid_utf8 = invalid_utf8;
+        self
+    }
+
+    // Chapters whose history goes back further than a shallow clone are dated by remote_history instead.
+    pub fn with_remote_history(mut self, remote_history: Option<RemoteHistory>) -> PostFinder {
+        self.remote_history = remote_history;
+        self
+    }
+
+    // Dates in notes on the commits that created chapters, in notes_ref (like "refs/notes/publish-dates"), override
+    // when they were created.
+    pub fn with_notes_ref(mut self, notes_ref: Option<String>) -> PostFinder {
+        self.notes_ref = notes_ref;
+        self
+    }
+
+    // The license, like "CC-BY-4.0", of posts that don't have a rights key in their front matter.
+    pub fn with_entry_rights(mut self, entry_rights: Option<String>) -> PostFinder {
+        self.entry_rights = entry_rights;
+        self
+    }
+
+    pub fn with_date_overrides(mut self, date_overrides: HashMap<PathBuf, DateOverride>) -> PostFinder {
+        self.date_overrides = date_overrides;
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Option<Duration>) -> PostFinder {
+        self.max_age = max_age;
+        self
+    }
+
+    // Searches for a limited number of entries only keep that many posts, plus up to chunk_size more between pruning,
+    // so large books don't hold every chapter's content at once. Defaults to 50.
+    pub fn with_search_chunk_size(mut self, chunk_size: usize) -> PostFinder {
+        self.search_chunk_size = chunk_size.max(1);
+        self
+    }
+
+    pub fn with_all_branches(mut self, all_branches: bool) -> PostFinder {
+        self.all_branches = all_branches;
+        self
+    }
+
+    pub fn with_changed_sections(mut self, changed_sections: bool) -> PostFinder {
+        self.changed_sections = changed_sections;
+        self
+    }
+
+    pub fn with_synthesized_chapters(mut self, include_synthesized_chapters: bool) -> PostFinder {
+        self.include_synthesized_chapters = include_synthesized_chapters;
+        self
+    }
+
+    // Fails when there's nothing to publish, rather than returning no posts.
+    #[must_use = "an empty or failed search should be reported rather than publishing nothing"]
+    pub fn search(&self, book: &Book, content_path: &Path, max_number_of_lines: Option<i64>, target_entries: i64, sort_field: SortField, sort_direction: SortDirection) -> Result<Vec<Post>, SearchError> {
+        self.search_filtered(book, content_path, max_number_of_lines, target_entries, (sort_field, sort_direction), |_| true)
+    }
+
+    // The same as search, only keeping posts that predicate is true for, like |post| post.word_count() > Some(500).
+    // Posts are checked once they've been blamed, so every field is filled in, and before they're sorted and limited to
+    // target_entries.
+    #[must_use = "an empty or failed search should be reported rather than publishing nothing"]
+    pub fn search_filtered<P>(&self, book: &Book, content_path: &Path, max_number_of_lines: Option<i64>, target_entries: i64, (sort_field, sort_direction): (SortField, SortDirection), predicate: P) -> Result<Vec<Post>, SearchError>
+    where
+        P: Fn(&Post) -> bool,
+    {
+        let collector = PostCollector::new(target_entries, sort_field, sort_direction, self.search_chunk_size)
+            .with_predicate(&predicate);
+        self.search_collecting(book, content_path, max_number_of_lines, collector)
+    }
+
+    // The same as search, calling progress(current, total) after each of the book's chapters is blamed, so long
+    // searches can show how far along they are.
+    #[must_use = "an empty or failed search should be reported rather than publishing nothing"]
+    pub fn search_with_progress<F>(&self, book: &Book, content_path: &Path, max_number_of_lines: Option<i64>, target_entries: i64, (sort_field, sort_direction): (SortField, SortDirection), progress: F) -> Result<Vec<Post>, SearchError>
+    where
+        F: Fn(usize, usize),
+    {
+        let total = book.iter().filter(|item| matches!(item, BookItem::Chapter(_))).count();
+        let collector = PostCollector::new(target_entries, sort_field, sort_direction, self.search_chunk_size)
+            .with_progress(&progress, total);
+        self.search_collecting(book, content_path, max_number_of_lines, collector)
+    }
+
+    // The chapters changed after from and up to to, like the commits a pull request merged, most recently modified
+    // first. Posts are dated and credited by those commits alone.
+    #[must_use = "an empty or failed search should be reported rather than publishing nothing"]
+    pub fn search_in_range(&self, book: &Book, content_path: &Path, max_number_of_lines: Option<i64>, target_entries: i64, from: Oid, to: Oid) -> Result<Vec<Post>, SearchError> {
+        let commit_range = self.commit_range.replace(Some((from, to)));
+        let posts = self.search(book, content_path, max_number_of_lines, target_entries, SortField::LastModified, SortDirection::Descending);
+        self.commit_range.set(commit_range);
+        posts
+    }
+
+    fn search_collecting(&self, book: &Book, content_path: &Path, max_number_of_lines: Option<i64>, collector: PostCollector) -> Result<Vec<Post>, SearchError> {
+        if !self.has_history() {
+            return Err(SearchError::NoHistory);
+        }
+        if let Some((from, to)) = self.commit_range.get() {
+            let is_range = self.repo().is_ok_and(|repo| from == to || repo.graph_descendant_of(to, from).unwrap_or(false));
+            if !is_range {
+                return Err(SearchError::InvalidCommitRange(from, to));
+            }
+        }
+        self.check_content_path(content_path)?;
+        self.warn_about_unknown_date_overrides(book);
+        let mut collector = collector.with_oldest(self.oldest_unexpired());
+        self.collect_posts(book, content_path, max_number_of_lines, &mut collector);
+        if let Some(error) = collector.error.take() {
+            return Err(SearchError::Chapter(error));
+        }
+        if collector.found == 0 {
+            return Err(SearchError::EmptyResult);
+        }
+        // A book whose posts are all too old just has nothing recent, which isn't an error.
+        self.log_expired(collector.expired);
+        Ok(collector.into_posts())
+    }
+
+    // Chapters outside of the repository can't be blamed, which would otherwise look like none of them being committed.
+    pub(crate) fn check_content_path(&self, content_path: &Path) -> Result<(), SearchError> {
+        let workdir = match self.repo().ok().and_then(|repo| repo.workdir()) {
+            Some(workdir) => workdir,
+            None => return Ok(()),
+        };
+        if let (Ok(canonical_content_path), Ok(canonical_workdir)) = (fs::canonicalize(content_path), fs::canonicalize(workdir)) {
+            // A src directory linked into another repository is blamed there, like symlinked chapters are.
+            if !canonical_content_path.starts_with(&canonical_workdir) && Repository::discover(&canonical_content_path).is_err() {
+                log::error!(
+                    "The book's source directory {} isn't inside the git repository at {}, so its chapters have no history. Check that the book's root is the root of the repository.",
+                    canonical_content_path.display(), canonical_workdir.display()
+                );
+                return Err(SearchError::ContentPathOutsideRepository(content_path.to_path_buf()));
+            }
+        }
+        Ok(())
+    }
+
+    // Date overrides for paths that aren't chapters are probably typos.
+    pub(crate) fn warn_about_unknown_date_overrides(&self, book: &Book) {
+        for path in self.unknown_date_overrides(book) {
+            log::warn!("There are dates for {}, which isn't a chapter in the book", path.display());
+        }
+    }
+
+    // The paths with date overrides that aren't chapters in book, sorted.
+    pub fn unknown_date_overrides(&self, book: &Book) -> Vec<&Path> {
+        let chapter_paths: HashSet<&Path> = book.iter()
+            .filter_map(|item| match item {
+                BookItem::Chapter(chapter) => chapter.path.as_deref(),
+                _ => None,
             })
             .collect();
-        posts.sort_by( |a, b| a.last_modified_date.cmp(&b.last_modified_date).reverse());
-        self.most_recent(posts, target_entries)
-    }
-
-    fn most_recent(&self, posts: Vec<Post>, target_entries: i64) -> Vec<Post> {
-        // get min_commits newest commit.
-        let mut revwalk = self.repo.revwalk().expect("Unable to create revwalk");
-        revwalk.set_sorting(git2::Sort::TIME).expect("Unable to sort the revwalk");
-        revwalk.push_head().expect("Unable to push head to the revwalk");
-        let walk = revwalk
-            .filter_map(|id| {
-                let id = id.ok()?;
-                let commit = self.repo.find_commit(id).ok()?;
-                Some(commit)
-            }).into_iter();
-        let commit = walk
-            .last()
-            .expect("No commits to take from");
-
-        let oldest_date = commit.time();
-
-        let entries = posts
-            .into_iter()
-            .filter(|post| post.last_modified_date >= oldest_date);
+        let mut unknown_paths: Vec<&Path> = self.date_overrides.keys()
+            .map(|path| path.as_path())
+            .filter(|path| !chapter_paths.contains(path))
+            .collect();
+        unknown_paths.sort();
+        unknown_paths
+    }
+
+    // Leaves out posts that haven't been modified for longer than max_age.
+    pub(crate) fn unexpired(&self, posts: Vec<Post>) -> Vec<Post> {
+        let oldest = match self.oldest_unexpired() {
+            Some(oldest) => oldest,
+            None => return posts,
+        };
+        let number_of_posts = posts.len();
+        let posts: Vec<Post> = posts.into_iter()
+            .filter(|post| post.last_modified_date.seconds() >= oldest)
+            .collect();
+        self.log_expired(number_of_posts - posts.len());
+        posts
+    }
+
+    // The oldest a post's last modification can be, in seconds since the epoch, without being left out by max_age.
+    fn oldest_unexpired(&self) -> Option<i64> {
+        let max_age = self.max_age?;
+        Some(Utc::now().timestamp().saturating_sub(i64::try_from(max_age.as_secs()).unwrap_or(i64::MAX)))
+    }
 
-        return if target_entries > 0 {
-            entries.take(target_entries as usize).collect()
-        } else {
-            entries.collect()
+    fn log_expired(&self, expired: usize) {
+        if let (Some(max_age), true) = (self.max_age, expired > 0) {
+            log::info!("Left out {} chapters that haven't been modified in {} days", expired, max_age.as_secs() / 86_400);
         }
+    }
 
+    // The same as search without max_age, for callers that combine posts from several searches before checking them.
+    pub(crate) fn find_posts(&self, book: &Book, content_path: &Path, max_number_of_lines: Option<i64>, target_entries: i64, sort_field: SortField, sort_direction: SortDirection) -> Vec<Post> {
+        if !self.has_history() {
+            return Vec::new();
+        }
+        let mut collector = PostCollector::new(target_entries, sort_field, sort_direction, self.search_chunk_size);
+        self.collect_posts(book, content_path, max_number_of_lines, &mut collector);
+        // Only searches can fail, so anything else that can't read a chapter just reports it.
+        if let Some(error) = collector.error.take() {
+            log::error!("{}", error);
+        }
+        collector.into_posts()
     }
 
-    fn post(&self, path: PathBuf, title: String, content_path: PathBuf, number_of_lines: Option<i64>) -> Option<Post> {
-        // Prepare our blame options
-        let mut opts = BlameOptions::new();
-        opts.track_copies_same_commit_moves(true)
-            .track_copies_same_commit_copies(true)
-            .first_parent(true);
+    fn collect_posts(&self, book: &Book, content_path: &Path, max_number_of_lines: Option<i64>, collector: &mut PostCollector) {
+        self.limit_history_to(content_path);
+        self.section_posts(&book.sections, 1, None, content_path, max_number_of_lines, collector);
+        log_skip_summary(&collector.skipped);
+    }
 
-        let blame_result = self.repo.blame_file(&path.as_path(), Some(&mut opts));
+    // The chapters searches leave out, by name, with why they're left out, in book order.
+    pub fn skipped_chapters(&self, book: &Book, content_path: &Path) -> Vec<(String, SkipReason)> {
+        let mut collector = PostCollector::new(0, SortField::LastModified, SortDirection::Descending, self.search_chunk_size);
+        self.limit_history_to(content_path);
+        self.section_posts(&book.sections, 1, None, content_path, None, &mut collector);
+        collector.skipped
+    }
 
-        let blame: Blame;
-        match blame_result {
-            Ok(bl) => blame = bl,
-            Err(_err) => {
-                return None
+    pub fn book_statistics(&self, book: &Book, content_path: &Path) -> BookStatistics {
+        let posts = self.find_posts(book, content_path, None, 0, SortField::LastModified, SortDirection::Descending);
+        self.statistics_for_posts(&posts)
+    }
+
+    // Statistics for every author of the book's chapters. They come from the same walk of the history that counts
+    // revisions, so no chapter is blamed or read. Chapters in other repositories, like submodules, aren't counted.
+    pub fn author_statistics(&self, book: &Book, content_path: &Path) -> HashMap<Author, AuthorStats> {
+        let workdir = match self.repo().ok().and_then(|repo| fs::canonicalize(repo.workdir()?).ok()) {
+            Some(workdir) => workdir,
+            None => return HashMap::new(),
+        };
+        self.limit_history_to(content_path);
+        let mut statistics: HashMap<Author, AuthorStats> = HashMap::new();
+        for item in book.iter() {
+            let chapter = match item {
+                BookItem::Chapter(chapter) => chapter,
+                _ => continue,
+            };
+            let (chapter_path, source_path) = match (chapter.path.as_ref(), chapter.source_path.as_ref()) {
+                (Some(chapter_path), Some(source_path)) => (chapter_path, source_path),
+                _ => continue,
+            };
+            if self.excluded_paths.iter().any(|excluded_path| excluded_path == chapter_path) {
+                continue;
+            }
+            let history = fs::canonicalize(content_path.join(source_path)).ok()
+                .and_then(|path| Some(path.strip_prefix(&workdir).ok()?.to_path_buf()))
+                .and_then(|path| self.path_history(&path));
+            let history = match history {
+                Some(history) => history,
+                None => continue,
+            };
+            for (author, (first, latest)) in history.author_dates.iter() {
+                let author_stats = statistics.entry(author.clone()).or_insert(AuthorStats {
+                    chapters_authored: 0,
+                    chapters_last_modified: 0,
+                    first_contribution: *first,
+                    latest_contribution: *latest,
+                });
+                author_stats.chapters_authored += 1;
+                author_stats.first_contribution = author_stats.first_contribution.min(*first);
+                author_stats.latest_contribution = author_stats.latest_contribution.max(*latest);
+            }
+            if let Some(author_stats) = history.last_author.as_ref().and_then(|author| statistics.get_mut(author)) {
+                author_stats.chapters_last_modified += 1;
             }
         }
+        statistics
+    }
 
-        let mut authors = HashSet::new();
-        let last_modified = blame.get_index(blame.len() - 1).expect("No blame at index 0").final_signature().when();
-        let created_at = blame.get_index(0).expect("no blame at last index").final_signature().when();
+    // The same as book_statistics, for posts that were already searched for.
+    pub(crate) fn statistics_for_posts(&self, posts: &[Post]) -> BookStatistics {
+        let authors: HashSet<&Author> = posts.iter().flat_map(|post| post.authors.keys()).collect();
 
-        for hunk in blame.iter() {
-            let signature = hunk.final_signature();
-            if let Some(name) = signature.name() {
-                authors.insert(Author {
-                    name: name.to_string(),
-                    email: signature.email().map(|email| email.to_string()),
-                });
+        let mut total_commits = 0;
+        let mut first_commit: Option<Time> = None;
+        let mut latest_commit: Option<Time> = None;
+        if let Some((repo, mut revwalk)) = self.repo().ok().and_then(|repo| Some((repo, repo.revwalk().ok()?))) {
+            let pushed = if self.all_branches { revwalk.push_glob("refs/heads/*") } else { revwalk.push_head() };
+            if self.has_history() && pushed.is_ok() {
+                for commit in revwalk.flatten().filter_map(|oid| repo.find_commit(oid).ok()) {
+                    let time = commit.time();
+                    total_commits += 1;
+                    first_commit = Some(first_commit.map_or(time, |first| first.min(time)));
+                    latest_commit = Some(latest_commit.map_or(time, |latest| latest.max(time)));
+                }
             }
         }
 
-        let id = &content_path.to_str().unwrap_or("").to_string();
+        BookStatistics {
+            total_chapters: posts.len(),
+            total_authors: authors.len(),
+            total_commits,
+            first_commit,
+            latest_commit,
+        }
+    }
+
+    // Every post modified after since, most recently modified first.
+    pub fn recently_updated_since(&self, book: &Book, content_path: &Path, since: DateTime<Utc>, max_number_of_lines: Option<i64>) -> Result<Vec<Post>, SearchError> {
+        if !self.has_history() {
+            return Err(SearchError::NoHistory);
+        }
+        self.check_content_path(content_path)?;
+        let mut collector = PostCollector::new(0, SortField::LastModified, SortDirection::Descending, self.search_chunk_size);
+        self.collect_posts(book, content_path, max_number_of_lines, &mut collector);
+        if let Some(error) = collector.error.take() {
+            return Err(SearchError::Chapter(error));
+        }
+        Ok(collector.into_posts()
+            .into_iter()
+            .filter(|post| post.last_modified_date.seconds() > since.timestamp())
+            .collect())
+    }
+
+    // Walks the book hierarchy, rolling chapters deeper than rollup_depth into their ancestor at that depth.
+    fn section_posts(&self, items: &[BookItem], depth: usize, part: Option<&str>, content_path: &Path, max_number_of_lines: Option<i64>, collector: &mut PostCollector) {
+        let mut part = part.map(|part| part.to_string());
+        for item in items {
+            if let BookItem::PartTitle(title) = item {
+                part = Some(title.to_string());
+            }
+            if collector.error.is_some() {
+                return;
+            }
+            if let BookItem::Chapter(chapter) = item {
+                let post = self.chapter_post(chapter, content_path, max_number_of_lines);
+                collector.report_progress();
+                let post = match post {
+                    Ok(mut post) => {
+                        post.section = part.clone().or_else(|| top_level_directory(&post.path));
+                        post.ancestors = chapter.parent_names.clone();
+                        Some(post)
+                    },
+                    Err(ChapterError::Skipped(reason)) => {
+                        collector.skipped.push((chapter.name.to_string(), reason));
+                        None
+                    }
+                    Err(ChapterError::Failed(error)) => {
+                        collector.error = Some(error);
+                        return;
+                    }
+                };
+
+                if self.rollup_depth > 0 && depth >= self.rollup_depth {
+                    // Every descendant is needed to roll up, whatever the search is limited to.
+                    let mut descendants = PostCollector::new(0, collector.sort_field, collector.sort_direction, self.search_chunk_size);
+                    descendants.progress = collector.progress;
+                    descendants.chapters = collector.chapters;
+                    descendants.total_chapters = collector.total_chapters;
+                    self.section_posts(&chapter.sub_items, depth + 1, part.as_deref(), content_path, max_number_of_lines, &mut descendants);
+                    collector.chapters = descendants.chapters;
+                    collector.skipped.append(&mut descendants.skipped);
+                    collector.error = descendants.error.take();
+                    match post {
+                        Some(mut post) => {
+                            for descendant in descendants.into_posts() {
+                                post.absorb(descendant);
+                            }
+                            collector.push(post);
+                        }
+                        // Like a draft version page, which would otherwise leave each of its pages an entry of its own.
+                        None if descendants.found > 0 => {
+                            log::debug!("Leaving out the {} chapter(s) under \"{}\", which has no post to roll them up into", descendants.found, chapter.name);
+                        }
+                        None => {}
+                    }
+                } else {
+                    if let Some(post) = post {
+                        collector.push(post);
+                    }
+                    self.section_posts(&chapter.sub_items, depth + 1, part.as_deref(), content_path, max_number_of_lines, collector);
+                }
+            }
+        }
+    }
+
+    fn chapter_post(&self, chapter: &Chapter, content_path: &Path, max_number_of_lines: Option<i64>) -> Result<Post, ChapterError> {
+        let chapter_path = match chapter.path.as_ref() {
+            Some(chapter_path) => chapter_path,
+            None => {
+                log::debug!("Skipping draft chapter \"{}\"", chapter.name);
+                return Err(ChapterError::Skipped(SkipReason::Draft));
+            }
+        };
+
+        if self.excluded_paths.iter().any(|excluded_path| excluded_path == chapter_path) {
+            log::debug!("Skipping excluded chapter \"{}\"", chapter.name);
+            return Err(ChapterError::Skipped(SkipReason::Excluded));
+        }
+
+        if let Some(source_path) = chapter.source_path.as_ref() {
+            let path = content_path.join(source_path.as_path());
+            return self.post_for_chapter(path, chapter.name.to_string(), chapter_path.to_path_buf(), max_number_of_lines)
+                .map_err(|error| match error {
+                    PostError::NoHistory(_) => ChapterError::Skipped(SkipReason::NoHistory),
+                    error => ChapterError::Failed(error),
+                });
+        }
 
-        let content: Option<String>;
-        if let Some(number_of_lines) = number_of_lines {
-            let mut markdown_content: String = String::new();
-            let file = File::open(&path).expect("Unable to open file");
-            let mut buf_reader = BufReader::new(file);
-            if number_of_lines == -1 {
-                buf_reader.read_to_string(&mut markdown_content).expect("Wasn't able to read text");
-            } else if number_of_lines > 0 {
-                markdown_content = buf_reader
-                    .lines()
-                    .take(number_of_lines as usize)
-                    .flat_map(|s| s.ok())
-                    .collect::<Vec<String>>()
-                    .join("\n")
-                    .to_string();
-            }
-
-            let mut options = Options::empty();
-            options.insert(Options::ENABLE_STRIKETHROUGH);
-            let parser = Parser::new_ext(markdown_content.as_str(), options);
-
-            let mut content_string = String::new();
-            html::push_html(&mut content_string, parser);
-            content = Some(content_string);
-        } else {
-            content = None;
+        if !self.include_synthesized_chapters || chapter.content.trim().is_empty() {
+            log::debug!("Skipping chapter \"{}\", which has no source file", chapter.name);
+            return Err(ChapterError::Skipped(SkipReason::Synthesized));
         }
+        self.synthesized_post(chapter, chapter_path.to_path_buf(), max_number_of_lines)
+            .ok_or(ChapterError::Skipped(SkipReason::Synthesized))
+    }
+
+    // Chapters generated by other preprocessors have no file to blame, so they're dated by HEAD instead.
+    fn synthesized_post(&self, chapter: &Chapter, content_path: PathBuf, number_of_lines: Option<i64>) -> Option<Post> {
+        let head_time = self.head_time()?;
+
+        let (title_directive, markdown) = strip_title_directive(&normalize_line_endings(&chapter.content));
+        let title = plain_text_title(&title_directive.unwrap_or_else(|| chapter.name.to_string()));
+        let content = number_of_lines
+            .map(|number_of_lines| self.render_content(&markdown, &title, number_of_lines, &content_path));
+        let (word_count, reading_time) = self.reading_statistics(&markdown);
 
         Some(Post {
+            id: content_path.to_str().unwrap_or("").to_string(),
             path: content_path,
-            last_modified_date: last_modified,
-            created_date: created_at,
-            authors,
+            last_modified_date: head_time,
+            created_date: head_time,
+            authors: HashMap::new(),
             title,
-            id: id.to_string(),
             content,
+            word_count: Some(word_count),
+            reading_time: Some(reading_time),
+            image: first_image(&markdown),
+            section: None,
+            ancestors: vec![],
+            changed_sections: vec![],
+            churn: 0,
+            rights: self.entry_rights.clone(),
+            is_git_tracked: false,
+            source_path: None,
+            created_commit: None,
+            last_commit: None,
+            revision_count: 0,
         })
     }
-}
 
-impl Post {
-    pub fn source_url(&self, base_url: Option<&Url>) -> Option<String> {
-        let url_string: String;
-        if let Some(base_url) = base_url {
-            url_string = base_url.join(self.path.to_str()?)
-                .ok()?
-                .to_string();
-        } else {
-            url_string = self.path.to_str().unwrap().to_string()
+    fn render_content(&self, markdown: &str, title: &str, number_of_lines: i64, content_path: &Path) -> String {
+        let markdown = if self.strip_h1_from_content { strip_title_heading(markdown, title) } else { markdown };
+        let max_line_bytes = match self.max_preview_line_bytes {
+            0 => usize::MAX,
+            max_line_bytes => max_line_bytes,
+        };
+        let (preview, shortened_lines) = markdown_preview(markdown.as_bytes(), number_of_lines, max_line_bytes);
+        if shortened_lines > 0 {
+            log::warn!(
+                "{} has {} lines longer than max_preview_line_bytes, which are cut short in its content",
+                content_path.display(), shortened_lines
+            );
         }
+        render_html(&preview)
+    }
 
-        Some(url_by_replacing_md_suffix(url_by_replacing_readme_md(url_string)))
+    // Counts the words in a chapter's markdown and estimates how many minutes it takes to read them.
+    fn reading_statistics(&self, markdown: &str) -> (u32, u32) {
+        let word_count = count_words_in_html(&render_markdown_for_counting(markdown, self.count_code_blocks));
+        let reading_time = word_count.div_ceil(self.words_per_minute);
+        (word_count, reading_time)
     }
-}
 
-fn url_by_replacing_md_suffix(url_string: String) -> String {
-    let re = Regex::new(r"md$").unwrap();
-    re.replace_all(url_string.as_str(), "html").to_string()
-}
+    fn blame_options(&self) -> BlameOptions {
+        let mut opts = BlameOptions::new();
+        opts.track_copies_same_commit_moves(self.blame_config.track_copies)
+            .track_copies_same_commit_copies(self.blame_config.track_copies)
+            .first_parent(self.blame_config.first_parent);
+        opts
+    }
+
+    // Blame options for files in the book's own repository, which stop at the depth boundary, or the date boundaries.
+    // Other repositories, like submodules, have their own histories the boundaries aren't part of.
+    fn repository_blame_options(&self) -> BlameOptions {
+        let mut opts = self.blame_options();
+        if let Some((from, to)) = self.commit_range.get() {
+            opts.oldest_commit(from).newest_commit(to);
+            return opts;
+        }
+        let (newest, before_since) = self.date_boundaries();
+        if let Some(newest) = newest {
+            opts.newest_commit(newest);
+        }
+        // Lines older than since are blamed on the commit before it, which dates them before since. The depth boundary
+        // is used instead when it's more recent.
+        let oldest = match (self.depth_boundary(), before_since) {
+            (Some(depth_boundary), Some(before_since)) => {
+                let depth_is_newer = self.repo().is_ok_and(|repo| repo.graph_descendant_of(depth_boundary, before_since).unwrap_or(false));
+                Some(if depth_is_newer { depth_boundary } else { before_since })
+            }
+            (depth_boundary, before_since) => depth_boundary.or(before_since),
+        };
+        if let Some(oldest) = oldest {
+            opts.oldest_commit(oldest);
+        }
+        opts
+    }
+
+    // The commits blame_config.until and blame_config.since limit blame to, when they're set.
+    fn date_boundaries(&self) -> (Option<Oid>, Option<Oid>) {
+        *self.date_boundaries.get_or_init(|| {
+            let (since, until) = (self.blame_config.since, self.blame_config.until);
+            if since.is_none() && until.is_none() {
+                return (None, None);
+            }
+            let commits = self.repo().ok().and_then(|repo| {
+                let mut revwalk = repo.revwalk().ok()?;
+                revwalk.push_head().ok()?;
+                revwalk.set_sorting(Sort::TIME).ok()?;
+                if self.blame_config.first_parent {
+                    revwalk.simplify_first_parent().ok()?;
+                }
+                Some(revwalk.flatten().filter_map(|oid| Some((oid, repo.find_commit(oid).ok()?.time()))).collect::<Vec<(Oid, Time)>>())
+            }).unwrap_or_default();
+            let mut in_range = commits.iter().skip_while(|(_, time)| until.is_some_and(|until| *time > until));
+            let newest = until.and(in_range.clone().next().map(|(oid, _)| *oid));
+            let before_since = since.and_then(|since| in_range.find(|(_, time)| *time < since).map(|(oid, _)| *oid));
+            (newest, before_since)
+        })
+    }
+
+    // The commit max_depth commits back from HEAD, or None when the history isn't that deep.
+    fn depth_boundary(&self) -> Option<Oid> {
+        *self.depth_boundary.get_or_init(|| {
+            if self.blame_config.max_depth == 0 {
+                return None;
+            }
+            let repo = self.repo().ok()?;
+            let mut revwalk = repo.revwalk().ok()?;
+            revwalk.push_head().ok()?;
+            if self.blame_config.first_parent {
+                revwalk.simplify_first_parent().ok()?;
+            }
+            let boundary = revwalk.nth(self.blame_config.max_depth)?.ok()?;
+            log::info!("History is deeper than {} commits, so blame stops at {}", self.blame_config.max_depth, boundary);
+            Some(boundary)
+        })
+    }
+
+    // Blames path, relative to the root of repo, warning when it takes longer than the blame timeout.
+    fn timed_blame<'repo>(&self, repo: &'repo Repository, path: &Path, opts: &mut BlameOptions) -> Result<Blame<'repo>, git2::Error> {
+        let start = Instant::now();
+        let blame = repo.blame_file(path, Some(opts));
+        let elapsed = start.elapsed();
+        if elapsed > self.blame_config.timeout {
+            log::warn!(
+                "Blaming {} took {} seconds, longer than blame_timeout_seconds. Lowering max_blame_depth makes it quicker.",
+                path.display(),
+                elapsed.as_secs()
+            );
+        }
+        blame
+    }
+
+    // The history of every path in the book's repository, from a single walk of its commits, which dates chapters too
+    // large to blame and counts every chapter's revisions.
+    fn path_histories(&self) -> &HashMap<PathBuf, PathHistory> {
+        self.path_histories.get_or_init(|| {
+            let pathspec = self.history_pathspec.get().map(PathBuf::as_path);
+            self.repo().ok().map(|repo| self.walk_path_histories(repo, true, pathspec)).unwrap_or_default()
+        })
+    }
+
+    // The history of path, relative to the root of the book's repository. Paths outside of the src directory, like the
+    // targets of symlinked chapters, aren't in the walk of the book's history, so theirs is walked on its own.
+    fn path_history(&self, path: &Path) -> Option<Cow<'_, PathHistory>> {
+        match self.history_pathspec.get() {
+            Some(pathspec) if !path.starts_with(pathspec) => {
+                let repo = self.repo().ok()?;
+                self.walk_path_histories(repo, true, Some(path)).remove(path).map(Cow::Owned)
+            }
+            _ => self.path_histories().get(path).map(Cow::Borrowed),
+        }
+    }
+
+    // Limits the walk of the book's history to content_path, the book's src directory, unless it's already been walked.
+    fn limit_history_to(&self, content_path: &Path) {
+        let workdir = match self.repo().ok().and_then(|repo| fs::canonicalize(repo.workdir()?).ok()) {
+            Some(workdir) => workdir,
+            None => return,
+        };
+        let pathspec = fs::canonicalize(content_path).ok()
+            .and_then(|content_path| Some(content_path.strip_prefix(&workdir).ok()?.to_path_buf()));
+        // A src directory at the root of the repository, or outside of it, can't narrow the walk.
+        if let Some(pathspec) = pathspec.filter(|pathspec| !pathspec.as_os_str().is_empty()) {
+            let _ = self.history_pathspec.set(pathspec);
+        }
+    }
+
+    // The history of every path in repo, relative to its root, from the commits that changed it, only diffing the
+    // paths under pathspec when there is one. Like blame, it follows blame_config's first_parent, since and until, and
+    // the commit range. The book's own repository also stops at the depth boundary. Renamed paths start a new history,
+    // unless blame_config's follow_renames is on.
+    fn walk_path_histories(&self, repo: &Repository, is_book_repository: bool, pathspec: Option<&Path>) -> HashMap<PathBuf, PathHistory> {
+        #[derive(Default)]
+        struct PathLog {
+            dates: Option<(Time, Time)>,
+            // Commits are walked newest first, so the last one seen created the path.
+            commit_ids: Option<(Oid, Oid)>,
+            authors: HashMap<Author, usize>,
+            // When each author first and last changed the path.
+            author_dates: HashMap<Author, (Time, Time)>,
+            // The author of the latest commit, with its time.
+            last_author: Option<(Time, Author)>,
+            revisions: u32,
+        }
 
-fn url_by_replacing_readme_md(url_string: String) -> String {
-    let re = Regex::new(r"README.md$").unwrap();
-    re.replace_all(url_string.as_str(), "index.html").to_string()
-}
\ No newline at end of file
+        let mut logs: HashMap<PathBuf, PathLog> = HashMap::new();
+        // The names renamed paths have now, by their older names, so their older commits count towards them.
+        let mut renamed_to: HashMap<PathBuf, PathBuf> = HashMap::new();
+        let walked = (|| -> Result<(), git2::Error> {
+            let mut revwalk = repo.revwalk()?;
+            match self.commit_range.get().filter(|_| is_book_repository) {
+                Some((from, to)) => {
+                    revwalk.push(to)?;
+                    revwalk.hide(from)?;
+                }
+                None => {
+                    revwalk.push_head()?;
+                    if let Some(depth_boundary) = self.depth_boundary().filter(|_| is_book_repository) {
+                        revwalk.hide(depth_boundary)?;
+                    }
+                }
+            }
+            // Merges don't date anything when merge_commit_dates is skip, so the commits they merge are walked instead.
+            let merges_date = self.blame_config.merge_commit_dates == MergeCommitDates::Use;
+            if self.blame_config.first_parent && merges_date {
+                revwalk.simplify_first_parent()?;
+            }
+            let (since, until) = (self.blame_config.since, self.blame_config.until);
+            for commit in revwalk.flatten().filter_map(|oid| repo.find_commit(oid).ok()) {
+                let time = commit.time();
+                // Merges only repeat the changes of the commits they merge, unless those aren't walked.
+                if (commit.parent_count() > 1 && !(self.blame_config.first_parent && merges_date))
+                    || since.is_some_and(|since| time < since) || until.is_some_and(|until| time > until) {
+                    continue;
+                }
+                let parent_tree = commit.parent(0).ok().and_then(|parent| parent.tree().ok());
+                let mut diff_options = DiffOptions::new();
+                if let Some(pathspec) = pathspec {
+                    diff_options.pathspec(pathspec);
+                }
+                let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut diff_options))?;
+                if self.blame_config.follow_renames {
+                    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+                }
+                let signature = commit.author();
+                let author = signature.name().map(|name| Author {
+                    name: name.to_string(),
+                    email: signature.email().map(|email| email.to_string()),
+                });
+                for delta in diff.deltas().filter(|delta| delta.status() != Delta::Deleted) {
+                    let path = match delta.new_file().path() {
+                        Some(path) => path,
+                        None => continue,
+                    };
+                    let path = renamed_to.get(path).cloned().unwrap_or_else(|| path.to_path_buf());
+                    if let (Delta::Renamed, Some(old_path)) = (delta.status(), delta.old_file().path()) {
+                        renamed_to.insert(old_path.to_path_buf(), path.clone());
+                    }
+                    let log = logs.entry(path).or_default();
+                    log.dates = Some(log.dates.map_or((time, time), |(first, last)| (first.min(time), last.max(time))));
+                    log.commit_ids = Some(log.commit_ids.map_or((commit.id(), commit.id()), |(_, last)| (commit.id(), last)));
+                    log.revisions = log.revisions.saturating_add(1);
+                    if let Some(author) = author.as_ref() {
+                        *log.authors.entry(author.clone()).or_insert(0) += 1;
+                        let dates = log.author_dates.entry(author.clone()).or_insert((time, time));
+                        *dates = (dates.0.min(time), dates.1.max(time));
+                        if log.last_author.as_ref().is_none_or(|(last, _)| time > *last) {
+                            log.last_author = Some((time, author.clone()));
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })();
+        if let Err(error) = walked {
+            log::warn!("Unable to walk the history of {}: {}", repo.path().display(), error.message());
+        }
+
+        logs.into_iter()
+            .filter_map(|(path, log)| {
+                let ((first, last), (first_id, last_id)) = (log.dates?, log.commit_ids?);
+                let mut history = PathHistory::new(first, last, log.authors.into_iter().collect(), log.revisions)
+                    .with_commit_ids(first_id.to_string(), last_id.to_string());
+                history.author_dates = log.author_dates;
+                history.last_author = log.last_author.map(|(_, author)| author);
+                Some((path, history))
+            })
+            .collect()
+    }
+
+    // When the newest commit merged by merge that changed path, relative to the root of repo, was made. None when merge
+    // isn't a merge commit, or none of the commits it merged changed path.
+    fn merged_change_time(&self, repo: &Repository, merge: Oid, path: &Path) -> Option<Time> {
+        if repo.find_commit(merge).ok()?.parent_count() < 2 {
+            return None;
+        }
+        let mut revwalk = repo.revwalk().ok()?;
+        revwalk.push(merge).ok()?;
+        revwalk.set_sorting(Sort::TIME).ok()?;
+        revwalk.flatten()
+            .filter_map(|oid| repo.find_commit(oid).ok())
+            .filter(|commit| commit.parent_count() < 2)
+            .find(|commit| {
+                let blob = commit.tree().ok().and_then(|tree| Some(tree.get_path(path).ok()?.id()));
+                let parent_blob = commit.parent(0).ok()
+                    .and_then(|parent| Some(parent.tree().ok()?.get_path(path).ok()?.id()));
+                blob.is_some() && blob != parent_blob
+            })
+            .map(|commit| commit.time())
+    }
+
+    // The blame of the file at path, relative to the root of the repository, in order of its lines. Files in
+    // submodules are blamed in the submodule's repository.
+    pub fn blame_hunks(&self, path: &Path) -> Result<Vec<BlameHunkInfo>, PostError> {
+        let repo = self.repo()?;
+        let mut opts = self.blame_options();
+        match self.timed_blame(repo, path, &mut self.repository_blame_options()) {
+            Ok(blame) => Ok(blame_hunk_infos(&blame)),
+            Err(error) if error.code() == ErrorCode::NotFound => {
+                let submodule = match find_submodule_repo(repo, path) {
+                    Some(submodule) => submodule,
+                    None => return Err(error.into()),
+                };
+                let submodule_path = submodule.workdir()
+                    .zip(repo.workdir())
+                    .and_then(|(submodule_workdir, workdir)| path.strip_prefix(submodule_workdir.strip_prefix(workdir).ok()?).ok());
+                match submodule_path {
+                    Some(submodule_path) => Ok(blame_hunk_infos(&self.timed_blame(&submodule, submodule_path, &mut opts)?)),
+                    None => Err(error.into()),
+                }
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    // The post for a single chapter, blamed at path, where the book's src directory is joined with the chapter's
+    // source path. content_path is the chapter's path within the book, which its id and link are made from. Previews
+    // are number_of_lines long, or the whole chapter when it's -1, and None leaves the content out. Fails with
+    // PostError::NoHistory when the chapter has no history, including when it doesn't exist. Searches also fill in the
+    // post's section and ancestors, which this can't.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(path = ?path)))]
+    pub fn post_for_chapter(&self, path: PathBuf, title: String, content_path: PathBuf, number_of_lines: Option<i64>) -> Result<Post, PostError> {
+        let text = match self.read_chapter(&path) {
+            Ok(text) => text,
+            Err(PostError::Read { source, .. }) if source.kind() == io::ErrorKind::NotFound => return Err(PostError::NoHistory(path)),
+            Err(error) => return Err(error),
+        };
+        self.blamed_post(path.clone(), title, content_path, number_of_lines, text)
+            .ok_or(PostError::NoHistory(path))
+    }
+
+    // The post for the chapter at path, whose markdown is text, or None when the chapter has no history. A text of None
+    // leaves the content out.
+    fn blamed_post(&self, path: PathBuf, title: String, content_path: PathBuf, number_of_lines: Option<i64>, text: Option<String>) -> Option<Post> {
+        let mut opts = self.blame_options();
+        let repo = self.repo().ok()?;
+
+        // Blaming a symlink only shows when the link was made, so symlinked chapters, or chapters in a symlinked src
+        // directory, are blamed as the file they link to, which might be in another repository.
+        let mut symlink_repo: Option<Repository> = None;
+        // Chapters linked from outside of any repository have no history, so they're dated by the filesystem instead.
+        let mut untracked_target = false;
+        // Chapter paths are relative to the book's root, but blame needs them relative to the repository's, which is
+        // further up when the book is in a subdirectory.
+        let canonical_workdir = repo.workdir().and_then(|workdir| fs::canonicalize(workdir).ok());
+        let canonical_path = fs::canonicalize(&path).ok();
+        let mut blame_path = canonical_workdir.as_ref()
+            .zip(canonical_path.as_ref())
+            .and_then(|(workdir, target)| target.strip_prefix(workdir).ok().map(Path::to_path_buf))
+            .unwrap_or_else(|| path.clone());
+        if let (Some(workdir), Some(target)) = (canonical_workdir.as_ref(), canonical_path.as_ref()) {
+            if !target.starts_with(workdir) {
+                let target_repo = Repository::discover(target).ok().and_then(|target_repo| {
+                    let target_workdir = fs::canonicalize(target_repo.workdir()?).ok()?;
+                    let relative_path = target.strip_prefix(target_workdir).ok()?.to_path_buf();
+                    Some((target_repo, relative_path))
+                });
+                match target_repo {
+                    Some((target_repo, relative_path)) => {
+                        symlink_repo = Some(target_repo);
+                        blame_path = relative_path;
+                    }
+                    None => {
+                        log::warn!(
+                            "{} links to {}, which isn't in a git repository, so it's dated by when the file was modified",
+                            path.display(), target.display()
+                        );
+                        untracked_target = true;
+                    }
+                }
+            }
+            log::debug!("Blaming {} as {}", path.display(), target.display());
+        }
+        let max_file_bytes = self.blame_config.max_file_bytes;
+        let too_large = max_file_bytes > 0 && fs::metadata(&path).is_ok_and(|metadata| metadata.len() > max_file_bytes);
+        if too_large && !untracked_target {
+            log::warn!(
+                "{} is larger than max_blame_file_bytes, so it's dated and credited by the commits that changed it instead of blamed",
+                path.display()
+            );
+        }
+        let blame_result = match symlink_repo.as_ref() {
+            _ if untracked_target || too_large => None,
+            Some(symlink_repo) => Some(self.timed_blame(symlink_repo, blame_path.as_path(), &mut opts)),
+            None => Some(self.timed_blame(repo, blame_path.as_path(), &mut self.repository_blame_options())),
+        };
+
+        // Files in a submodule aren't tracked by this repository, so they have to be blamed in the submodule's.
+        let mut submodule_repo: Option<Repository> = None;
+        let blame: Option<Blame> = match blame_result {
+            None => None,
+            Some(Ok(bl)) => Some(bl),
+            Some(Err(err)) if err.code() == ErrorCode::NotFound && symlink_repo.is_none() => {
+                submodule_repo = find_submodule_repo(repo, &blame_path);
+                match submodule_repo.as_ref() {
+                    Some(submodule) => {
+                        let submodule_blame = submodule.workdir()
+                            .zip(repo.workdir())
+                            .ok_or_else(|| "it has no working directory".to_string())
+                            .and_then(|(submodule_workdir, workdir)| submodule_workdir.strip_prefix(workdir).map_err(|err| err.to_string()))
+                            .and_then(|submodule_path| blame_path.strip_prefix(submodule_path).map_err(|err| err.to_string()))
+                            .and_then(|relative_path| self.timed_blame(submodule, relative_path, &mut opts).map_err(|err| err.to_string()));
+                        match submodule_blame {
+                            Ok(bl) => Some(bl),
+                            // Like an uninitialized submodule, the chapter is still published, dated by the filesystem.
+                            Err(err) => {
+                                log::warn!(
+                                    "Couldn't blame {} in the submodule at {}: {}",
+                                    blame_path.display(), submodule.path().display(), err
+                                );
+                                None
+                            }
+                        }
+                    }
+                    // Not in a submodule, but shallow clones also fail to blame files with history older than the clone.
+                    None => None,
+                }
+            }
+            Some(Err(_err)) => {
+                return None
+            }
+        };
+
+        let blamed_repo = submodule_repo.as_ref().or(symlink_repo.as_ref()).unwrap_or(repo);
+        // Submodules and other repositories have paths relative to their own roots.
+        let blamed_path = blamed_repo.workdir()
+            .and_then(|workdir| fs::canonicalize(&path).ok()?.strip_prefix(fs::canonicalize(workdir).ok()?).ok().map(Path::to_path_buf))
+            .unwrap_or_else(|| blame_path.to_path_buf());
+        let merge_dates: HashMap<Oid, Time> = match (self.blame_config.merge_commit_dates, blame.as_ref()) {
+            (MergeCommitDates::Skip, Some(blame)) => blame.iter()
+                .map(|hunk| hunk.final_commit_id())
+                .collect::<HashSet<Oid>>()
+                .into_iter()
+                .filter_map(|commit| Some((commit, self.merged_change_time(blamed_repo, commit, &blamed_path)?)))
+                .collect(),
+            _ => HashMap::new(),
+        };
+        let in_repository = symlink_repo.is_none() && submodule_repo.is_none();
+        let hunk_date = |hunk: &BlameHunk| merge_dates.get(&hunk.final_commit_id()).copied().unwrap_or_else(|| hunk.final_signature().when());
+
+        // Shallow clones either can't blame a chapter at all, or blame lines older than the clone on the oldest commit
+        // they have, which has the wrong date and author. Those come from the provider instead when there's a fallback.
+        let shallow_boundary = shallow_boundary(repo);
+        let truncated = match blame.as_ref() {
+            Some(blame) => blame.iter().any(|hunk| shallow_boundary.contains(&hunk.final_commit_id())),
+            None => repo.is_shallow(),
+        };
+        let remote_history = self.remote_history.as_ref()
+            .filter(|_| truncated && !untracked_target && symlink_repo.is_none() && submodule_repo.is_none())
+            .and_then(|remote_history| remote_history.path_history(repo, &blame_path));
+        // Chapters too large to blame, or empty ones without any lines to blame, have the same kind of history as the
+        // provider's, from the local commits.
+        let empty = blame.as_ref().is_some_and(|blame| blame.is_empty());
+        let remote_history = remote_history.or_else(|| {
+            if (too_large || empty) && !untracked_target {
+                match symlink_repo.as_ref() {
+                    Some(symlink_repo) => self.walk_path_histories(symlink_repo, false, Some(&blame_path)).remove(&blame_path),
+                    None => self.path_history(&blame_path).map(Cow::into_owned),
+                }
+            } else {
+                None
+            }
+        });
+
+        let mut authors: HashMap<Author, usize> = HashMap::new();
+        // Hunks are in the order of their lines, not their dates, so the oldest and newest can be anywhere.
+        let hunk_dates = blame.as_ref().map(|blame| blame.iter().map(|hunk| hunk_date(&hunk)).collect::<Vec<Time>>());
+        let (mut created_at, mut last_modified) = match hunk_dates.as_ref().and_then(|dates| Some((*dates.iter().min()?, *dates.iter().max()?))) {
+            Some(dates) => dates,
+            None => match remote_history.as_ref() {
+                Some(remote_history) => (remote_history.first_commit(), remote_history.last_commit()),
+                // Chapters that haven't been committed yet are still published, dated by the filesystem.
+                None => {
+                    if !untracked_target {
+                        log::info!("{} has no git history, so it's dated by when the file was modified", path.display());
+                    }
+                    file_dates(&path)?
+                }
+            },
+        };
+        let (since, until) = (self.blame_config.since, self.blame_config.until);
+        let in_blame_range = |time: Time| since.is_none_or(|since| time >= since) && until.is_none_or(|until| time <= until);
+        if let Some(blame) = blame.as_ref().filter(|_| since.is_some() || until.is_some()) {
+            let dates: Vec<Time> = blame.iter()
+                .map(|hunk| hunk_date(&hunk))
+                .filter(|time| in_blame_range(*time))
+                .collect();
+            match (dates.iter().min(), dates.iter().max()) {
+                (Some(first), Some(last)) => {
+                    created_at = *first;
+                    last_modified = *last;
+                }
+                _ => {
+                    log::debug!("{} has no lines changed between blame_since and blame_until, leaving it out", content_path.display());
+                    return None;
+                }
+            }
+        }
+        if let Some((from, to)) = self.commit_range.get() {
+            // Lines from before the range are blamed on its first commit.
+            let changed_dates: Vec<Time> = blame.iter().flat_map(|blame| blame.iter())
+                .filter(|hunk| hunk.final_commit_id() != from)
+                .map(|hunk| hunk_date(&hunk))
+                .collect();
+            match changed_dates.iter().max() {
+                Some(latest) if in_repository => last_modified = *latest,
+                // Chapters too large to blame were only dated by the commits in the range.
+                None if in_repository && blame.is_none() && remote_history.is_some() => {}
+                _ => {
+                    log::debug!("{} wasn't changed between {} and {}, leaving it out", content_path.display(), from, to);
+                    return None;
+                }
+            }
+        }
+        let is_git_tracked = blame.is_some() || remote_history.is_some();
+        if let Some(remote_history) = remote_history.as_ref() {
+            created_at = created_at.min(remote_history.first_commit());
+            last_modified = last_modified.max(remote_history.last_commit());
+        }
+        // The chapter was created by the oldest commit that changed it. Chapters in other repositories, like
+        // submodules, aren't in the walked history, so they use the commit their oldest lines come from instead.
+        let walked_history = if in_repository && is_git_tracked { self.path_history(&blame_path) } else { None };
+        let creating_commit = walked_history.as_ref()
+            .and_then(|history| Oid::from_str(history.first_commit_id.as_deref()?).ok())
+            .or_else(|| {
+                blame.iter().flat_map(|blame| blame.iter())
+                    .min_by_key(|hunk| hunk_date(hunk))
+                    .map(|hunk| hunk.final_commit_id())
+            });
+        // Dates are taken from the chapter's front matter, which is read below, then the date overrides, then notes, then
+        // the history itself.
+        if let (Some(notes_ref), Some(creating_commit)) = (self.notes_ref.as_ref(), creating_commit) {
+            if let Some(published) = note_date(blamed_repo, notes_ref, creating_commit, &blamed_path) {
+                created_at = published;
+            }
+        }
+        if let Some(date_override) = self.date_overrides.get(&content_path) {
+            created_at = date_override.published.unwrap_or(created_at);
+            last_modified = date_override.updated.unwrap_or(last_modified);
+        }
+
+        let max_hunks = match self.blame_config.max_hunks {
+            0 => usize::MAX,
+            max_hunks => max_hunks,
+        };
+        if blame.as_ref().map_or(0, |blame| blame.len()) > max_hunks {
+            log::debug!("{} has more than {} blame hunks, only crediting the authors of the first {}", content_path.display(), max_hunks, max_hunks);
+        }
+        for hunk in blame.iter().flat_map(|blame| blame.iter()).take(max_hunks) {
+            if remote_history.is_some() && shallow_boundary.contains(&hunk.final_commit_id()) {
+                continue;
+            }
+            if self.commit_range.get().is_some_and(|(from, _)| hunk.final_commit_id() == from) {
+                continue;
+            }
+            let signature = hunk.final_signature();
+            if !in_blame_range(hunk_date(&hunk)) {
+                continue;
+            }
+            if let Some(name) = signature.name() {
+                let author = Author {
+                    name: name.to_string(),
+                    email: signature.email().map(|email| email.to_string()),
+                };
+                *authors.entry(author).or_insert(0) += hunk.lines_in_hunk();
+            }
+        }
+        // The provider only knows how many commits each author made, so those stand in for their lines.
+        for (author, commits) in remote_history.iter().flat_map(|remote_history| remote_history.authors.iter().cloned()) {
+            authors.entry(author).or_insert(commits);
+        }
+
+        // The lines changed by the chapter's latest commit, as (first line, number of lines). Commits are compared by
+        // ancestry rather than date, since dates can be out of order after a rebase.
+        let latest_commit = blame.iter().flat_map(|blame| blame.iter())
+            .map(|hunk| hunk.final_commit_id())
+            .reduce(|latest, commit| {
+                if blamed_repo.graph_descendant_of(commit, latest).unwrap_or(false) { commit } else { latest }
+            });
+        let changed_lines: Vec<(usize, usize)> = blame.iter().flat_map(|blame| blame.iter())
+            .filter(|hunk| Some(hunk.final_commit_id()) == latest_commit)
+            .map(|hunk| (hunk.final_start_line(), hunk.lines_in_hunk()))
+            .collect();
+        // Lines older than a shallow clone or a commit range are blamed on a commit that didn't change them, so the
+        // history's commits are used instead, when there are any.
+        let changed_by = |commit: Oid| {
+            !(remote_history.is_some() && shallow_boundary.contains(&commit))
+                && self.commit_range.get().is_none_or(|(from, _)| commit != from)
+        };
+        let created_commit = creating_commit
+            .filter(|commit| changed_by(*commit))
+            .map(|commit| commit.to_string())
+            .or_else(|| remote_history.as_ref()?.first_commit_id.clone());
+        let last_commit = latest_commit
+            .filter(|commit| changed_by(*commit))
+            .map(|commit| commit.to_string())
+            .or_else(|| remote_history.as_ref()?.last_commit_id.clone());
+        // Chapters in other repositories, like submodules, aren't in the book's history.
+        let revision_count = match remote_history.as_ref() {
+            Some(remote_history) => remote_history.revisions,
+            None => walked_history.as_ref().map_or(0, |history| history.revisions),
+        };
+
+        let id = &content_path.to_str().unwrap_or("").to_string();
+
+        let has_content = text.is_some();
+        // Checkouts with CRLF line endings should produce the same content as ones with LF.
+        let markdown = normalize_line_endings(text.as_deref().unwrap_or(""));
+        // Blamed line numbers are only meaningful before anything is stripped from the markdown.
+        let changed_sections = if self.changed_sections { changed_sections(&markdown, &changed_lines) } else { vec![] };
+        let (front_matter, markdown) = strip_front_matter(&markdown);
+        let rights = front_matter.as_ref()
+            .and_then(|front_matter| front_matter.get("rights")?.as_str().map(|rights| rights.to_string()))
+            .or_else(|| self.entry_rights.clone());
+        // Dates in the chapter's own front matter take precedence over the date overrides.
+        let front_matter_date = |key: &str| {
+            let date = match front_matter.as_ref()?.get(key)? {
+                toml::Value::String(date) => date.to_string(),
+                toml::Value::Datetime(date) => date.to_string(),
+                value => value.to_string(),
+            };
+            let time = parse_time(&date);
+            if time.is_none() {
+                log::warn!("Ignoring the {} date {} in the front matter of {}, expected a date like 2015-03-01T12:00:00Z", key, date, path.display());
+            }
+            time
+        };
+        created_at = front_matter_date("published").unwrap_or(created_at);
+        last_modified = front_matter_date("updated").unwrap_or(last_modified);
+        // A {{#title}} directive overrides the chapter name, the same as it does in mdbook's html renderer.
+        let (title_directive, markdown) = strip_title_directive(&markdown);
+        let mut title = plain_text_title(&title_directive.unwrap_or(title));
+        // Chapters listed in SUMMARY.md without a name would otherwise become entries without a title.
+        if title.is_empty() {
+            title = extract_title_from_markdown(&markdown)
+                .map(|heading| plain_text_title(&heading))
+                .filter(|heading| !heading.is_empty())
+                .or_else(|| path.file_stem().and_then(|stem| stem.to_str()).map(|stem| stem.to_string()))
+                .unwrap_or(title);
+        }
+
+        let content = number_of_lines
+            .filter(|_| has_content)
+            .map(|number_of_lines| self.render_content(&markdown, &title, number_of_lines, &content_path))
+            .map(|content| {
+                if changed_sections.is_empty() {
+                    content
+                } else {
+                    format!("{}<p>Updated sections: {}</p>\n", content, html_escape::encode_text(&changed_sections.join(", ")))
+                }
+            });
+
+        let (word_count, reading_time) = self.reading_statistics(&markdown);
+        let churn = blame.as_ref().map_or(0, |blame| u32::try_from(blame.len()).unwrap_or(u32::MAX));
+
+        Some(Post {
+            path: content_path,
+            last_modified_date: last_modified,
+            created_date: created_at,
+            authors,
+            title,
+            id: id.to_string(),
+            content,
+            word_count: Some(word_count).filter(|_| has_content),
+            reading_time: Some(reading_time).filter(|_| has_content),
+            image: first_image(&markdown),
+            section: None,
+            ancestors: vec![],
+            changed_sections,
+            churn,
+            rights,
+            is_git_tracked,
+            source_path: Some(blame_path).filter(|_| is_git_tracked),
+            created_commit,
+            last_commit,
+            revision_count,
+        })
+    }
+
+    // Reads a chapter's markdown without any byte order mark. Chapters that aren't valid UTF-8 are handled
+    // according to invalid_utf8, and None means the chapter's content is left out.
+    fn read_chapter(&self, path: &Path) -> Result<Option<String>, PostError> {
+        let bytes = fs::read(path).map_err(|source| PostError::Read { path: path.to_path_buf(), source })?;
+        let text = match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(error) => match self.invalid_utf8 {
+                InvalidUtf8::Lossy => {
+                    log::warn!("{} isn't valid UTF-8, replacing the invalid bytes", path.display());
+                    String::from_utf8_lossy(error.as_bytes()).to_string()
+                }
+                InvalidUtf8::Skip => {
+                    log::warn!("{} isn't valid UTF-8, leaving out its content", path.display());
+                    return Ok(None);
+                }
+                InvalidUtf8::Error => return Err(PostError::InvalidUtf8(path.to_path_buf())),
+            },
+        };
+        Ok(Some(text.strip_prefix('\u{feff}').map(|text| text.to_string()).unwrap_or(text)))
+    }
+}
+
+// Where the processors get posts from. PostFinder reads them from the git history, and other implementations can stand
+// in for it, like a fake history in tests or a custom mdbook driver.
+pub trait HistoryProvider {
+    // Gets the history ready, like opening the repository, so problems are reported before searching.
+    fn open(&self) -> Result<(), git2::Error> {
+        Ok(())
+    }
+
+    fn has_history(&self) -> bool {
+        true
+    }
+
+    // When the history was last changed, which dates entries deleted from the feed. None when it isn't known.
+    fn head_time(&self) -> Option<Time> {
+        None
+    }
+
+    // The same as PostFinder::search_with_progress.
+    fn search_with_progress(&self, book: &Book, content_path: &Path, max_number_of_lines: Option<i64>, target_entries: i64, sort: (SortField, SortDirection), progress: &dyn Fn(usize, usize)) -> Result<Vec<Post>, SearchError>;
+
+    // Posts of another book that are merged with the main book's, so there being none isn't an error.
+    fn find_posts(&self, book: &Book, content_path: &Path, max_number_of_lines: Option<i64>, sort: (SortField, SortDirection)) -> Vec<Post> {
+        self.search_with_progress(book, content_path, max_number_of_lines, 0, sort, &|_, _| {}).unwrap_or_default()
+    }
+
+    // Statistics for {{#author_stats}}, which aren't known without a repository.
+    fn author_statistics(&self, _book: &Book, _content_path: &Path) -> HashMap<Author, AuthorStats> {
+        HashMap::new()
+    }
+
+    // Statistics for {{#book_stats}}. Without a repository, nothing is known about commits.
+    fn statistics_for_posts(&self, posts: &[Post]) -> BookStatistics {
+        BookStatistics {
+            total_chapters: posts.len(),
+            total_authors: posts.iter().flat_map(|post| post.authors.keys()).collect::<HashSet<&Author>>().len(),
+            total_commits: 0,
+            first_commit: None,
+            latest_commit: None,
+        }
+    }
+}
+
+impl HistoryProvider for PostFinder {
+    fn open(&self) -> Result<(), git2::Error> {
+        PostFinder::open(self)
+    }
+
+    fn has_history(&self) -> bool {
+        PostFinder::has_history(self)
+    }
+
+    fn head_time(&self) -> Option<Time> {
+        PostFinder::head_time(self)
+    }
+
+    fn search_with_progress(&self, book: &Book, content_path: &Path, max_number_of_lines: Option<i64>, target_entries: i64, sort: (SortField, SortDirection), progress: &dyn Fn(usize, usize)) -> Result<Vec<Post>, SearchError> {
+        PostFinder::search_with_progress(self, book, content_path, max_number_of_lines, target_entries, sort, progress)
+    }
+
+    fn find_posts(&self, book: &Book, content_path: &Path, max_number_of_lines: Option<i64>, (sort_field, sort_direction): (SortField, SortDirection)) -> Vec<Post> {
+        self.unexpired(PostFinder::find_posts(self, book, content_path, max_number_of_lines, 0, sort_field, sort_direction))
+    }
+
+    fn author_statistics(&self, book: &Book, content_path: &Path) -> HashMap<Author, AuthorStats> {
+        PostFinder::author_statistics(self, book, content_path)
+    }
+
+    fn statistics_for_posts(&self, posts: &[Post]) -> BookStatistics {
+        PostFinder::statistics_for_posts(self, posts)
+    }
+}
+
+pub(crate) fn sort_posts(posts: &mut [Post], sort_field: SortField, sort_direction: SortDirection) {
+    posts.sort_by(|a, b| compare_posts(a, b, sort_field, sort_direction));
+}
+
+fn compare_posts(a: &Post, b: &Post, sort_field: SortField, sort_direction: SortDirection) -> Ordering {
+    let ordering = match sort_field {
+        SortField::LastModified => a.last_modified_date.cmp(&b.last_modified_date),
+        SortField::Created => a.created_date.cmp(&b.created_date),
+        SortField::Title => a.title.cmp(&b.title),
+    };
+    match sort_direction {
+        SortDirection::Ascending => ordering,
+        SortDirection::Descending => ordering.reverse(),
+    }
+}
+
+// The posts found by a search, in sort order. Searches limited to target_entries only keep that many, so searching a
+// book with thousands of chapters for a few entries doesn't hold every chapter's content in memory.
+struct PostCollector<'a> {
+    // The post that sorts last is at the top, ready to be dropped.
+    heap: BinaryHeap<RankedPost>,
+    capacity: Option<usize>,
+    // How many posts beyond capacity are held before the heap is pruned back down to it.
+    chunk_size: usize,
+    sort_field: SortField,
+    sort_direction: SortDirection,
+    // Posts last modified before this, in seconds since the epoch, are left out.
+    oldest: Option<i64>,
+    // Posts this is false for are left out.
+    predicate: Option<&'a dyn Fn(&Post) -> bool>,
+    // Called with the number of chapters searched so far and the number in the book, after each chapter.
+    progress: Option<&'a dyn Fn(usize, usize)>,
+    chapters: usize,
+    total_chapters: usize,
+    // Every post found, including ones that were left out or dropped.
+    found: usize,
+    expired: usize,
+    skipped: Vec<(String, SkipReason)>,
+    // The first chapter that couldn't be made into a post, which stops the search.
+    error: Option<PostError>,
+}
+
+struct RankedPost {
+    post: Post,
+    // Where the post is in the book, so posts that sort the same stay in book order like they do with sort_posts.
+    index: usize,
+    sort_field: SortField,
+    sort_direction: SortDirection,
+}
+
+impl Ord for RankedPost {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_posts(&self.post, &other.post, self.sort_field, self.sort_direction)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for RankedPost {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for RankedPost {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RankedPost {}
+
+impl<'a> PostCollector<'a> {
+    // 0 or less keeps every post.
+    fn new(target_entries: i64, sort_field: SortField, sort_direction: SortDirection, chunk_size: usize) -> PostCollector<'a> {
+        PostCollector {
+            heap: BinaryHeap::new(),
+            capacity: usize::try_from(target_entries).ok().filter(|capacity| *capacity > 0),
+            chunk_size,
+            sort_field,
+            sort_direction,
+            oldest: None,
+            predicate: None,
+            progress: None,
+            chapters: 0,
+            total_chapters: 0,
+            found: 0,
+            expired: 0,
+            skipped: vec![],
+            error: None,
+        }
+    }
+
+    fn with_oldest(mut self, oldest: Option<i64>) -> PostCollector<'a> {
+        self.oldest = oldest;
+        self
+    }
+
+    fn with_predicate(mut self, predicate: &'a dyn Fn(&Post) -> bool) -> PostCollector<'a> {
+        self.predicate = Some(predicate);
+        self
+    }
+
+    fn with_progress(mut self, progress: &'a dyn Fn(usize, usize), total: usize) -> PostCollector<'a> {
+        self.progress = Some(progress);
+        self.total_chapters = total;
+        self
+    }
+
+    fn report_progress(&mut self) {
+        self.chapters += 1;
+        if let Some(progress) = self.progress {
+            progress(self.chapters, self.total_chapters);
+        }
+    }
+
+    fn push(&mut self, post: Post) {
+        self.found += 1;
+        if self.oldest.is_some_and(|oldest| post.last_modified_date.seconds() < oldest) {
+            self.expired += 1;
+            return;
+        }
+        if self.predicate.is_some_and(|predicate| !predicate(&post)) {
+            return;
+        }
+        self.heap.push(RankedPost { post, index: self.found, sort_field: self.sort_field, sort_direction: self.sort_direction });
+        if let Some(capacity) = self.capacity {
+            if self.heap.len() >= capacity.saturating_add(self.chunk_size) {
+                while self.heap.len() > capacity {
+                    self.heap.pop();
+                }
+            }
+        }
+    }
+
+    fn into_posts(self) -> Vec<Post> {
+        let capacity = self.capacity.unwrap_or(usize::MAX);
+        self.heap.into_sorted_vec()
+            .into_iter()
+            .take(capacity)
+            .map(|ranked| ranked.post)
+            .collect()
+    }
+}
+
+// git2::Time isn't serializable, so posts' dates are RFC 3339 timestamps in their original offsets.
+mod time_serde {
+    use chrono::{DateTime, FixedOffset, TimeZone};
+    use git2::Time;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(time: &Time, serializer: S) -> Result<S::Ok, S::Error> {
+        let offset = FixedOffset::east_opt(time.offset_minutes() * 60).unwrap_or_else(|| FixedOffset::east(0));
+        let date = offset.timestamp_opt(time.seconds(), 0).single()
+            .ok_or_else(|| serde::ser::Error::custom(format!("{} is out of range", time.seconds())))?;
+        serializer.serialize_str(&date.to_rfc3339())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Time, D::Error> {
+        let date = DateTime::parse_from_rfc3339(&String::deserialize(deserializer)?).map_err(de::Error::custom)?;
+        Ok(Time::new(date.timestamp(), date.offset().local_minus_utc() / 60))
+    }
+}
+
+// Authors aren't strings, so they can't be map keys in JSON. They're a list of authors and their number of lines
+// instead, like remote histories.
+mod authors_serde {
+    use std::collections::HashMap;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use super::Author;
+
+    pub(super) fn serialize<S: Serializer>(authors: &HashMap<Author, usize>, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut authors: Vec<(&Author, &usize)> = authors.iter().collect();
+        authors.sort();
+        authors.serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<Author, usize>, D::Error> {
+        Ok(Vec::<(Author, usize)>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl Post {
+    // A post for the chapter at path, relative to the src directory, for building posts without a repository.
+    // Its dates are the epoch until they're set with with_dates.
+    pub fn new(path: impl Into<PathBuf>, title: impl Into<String>) -> Post {
+        let path = path.into();
+        Post {
+            id: path.to_str().unwrap_or("").to_string(),
+            path,
+            last_modified_date: Time::new(0, 0),
+            created_date: Time::new(0, 0),
+            authors: HashMap::new(),
+            title: title.into(),
+            content: None,
+            word_count: None,
+            reading_time: None,
+            image: None,
+            section: None,
+            ancestors: vec![],
+            changed_sections: vec![],
+            churn: 0,
+            rights: None,
+            is_git_tracked: false,
+            source_path: None,
+            created_commit: None,
+            last_commit: None,
+            revision_count: 0,
+        }
+    }
+
+    pub fn with_dates(mut self, created_date: Time, last_modified_date: Time) -> Post {
+        self.created_date = created_date;
+        self.last_modified_date = last_modified_date;
+        self
+    }
+
+    // Authors with the number of lines they wrote, which orders them in feeds.
+    pub fn with_authors(mut self, authors: HashMap<Author, usize>) -> Post {
+        self.authors = authors;
+        self
+    }
+
+    // The license the post is published under, like "CC-BY-4.0".
+    pub fn with_rights(mut self, rights: impl Into<String>) -> Post {
+        self.rights = Some(rights.into());
+        self
+    }
+
+    // The html shown as the post's content.
+    pub fn with_content(mut self, content: impl Into<String>) -> Post {
+        self.content = Some(content.into());
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn last_modified_date(&self) -> Time {
+        self.last_modified_date
+    }
+
+    pub fn created_date(&self) -> Time {
+        self.created_date
+    }
+
+    pub fn authors(&self) -> &HashMap<Author, usize> {
+        &self.authors
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn content(&self) -> Option<&str> {
+        self.content.as_deref()
+    }
+
+    pub fn word_count(&self) -> Option<u32> {
+        self.word_count
+    }
+
+    pub fn reading_time(&self) -> Option<u32> {
+        self.reading_time
+    }
+
+    pub fn image(&self) -> Option<&str> {
+        self.image.as_deref()
+    }
+
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    pub fn ancestors(&self) -> &[String] {
+        &self.ancestors
+    }
+
+    pub fn changed_sections(&self) -> &[String] {
+        &self.changed_sections
+    }
+
+    pub fn churn(&self) -> u32 {
+        self.churn
+    }
+
+    pub fn rights(&self) -> Option<&str> {
+        self.rights.as_deref()
+    }
+
+    pub fn is_git_tracked(&self) -> bool {
+        self.is_git_tracked
+    }
+
+    pub fn source_path(&self) -> Option<&Path> {
+        self.source_path.as_deref()
+    }
+
+    pub fn created_commit(&self) -> Option<&str> {
+        self.created_commit.as_deref()
+    }
+
+    pub fn last_commit(&self) -> Option<&str> {
+        self.last_commit.as_deref()
+    }
+
+    pub fn revision_count(&self) -> u32 {
+        self.revision_count
+    }
+
+    // Merges a descendant chapter's history into this post.
+    fn absorb(&mut self, descendant: Post) {
+        if descendant.last_modified_date > self.last_modified_date {
+            self.last_modified_date = descendant.last_modified_date;
+            self.last_commit = descendant.last_commit;
+        }
+        // Commits that changed several of the chapters are counted for each of them.
+        self.revision_count = self.revision_count.saturating_add(descendant.revision_count);
+        for (author, lines) in descendant.authors {
+            *self.authors.entry(author).or_insert(0) += lines;
+        }
+    }
+
+    // Authors ordered by the number of lines they own, most first. Ties are broken by name.
+    pub(crate) fn ranked_authors(&self) -> Vec<&Author> {
+        let mut ranked: Vec<(&Author, &usize)> = self.authors.iter().collect();
+        ranked.sort_by(|(a, a_lines), (b, b_lines)| {
+            b_lines.cmp(a_lines)
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.email.cmp(&b.email))
+        });
+        ranked.into_iter().map(|(author, _)| author).collect()
+    }
+
+    pub(crate) fn selected_authors(&self, entry_authors: &EntryAuthors) -> Vec<&Author> {
+        let ranked = self.ranked_authors();
+        match entry_authors {
+            EntryAuthors::All => ranked,
+            EntryAuthors::Primary => ranked.into_iter().take(1).collect(),
+            EntryAuthors::Top(count) => ranked.into_iter().take(*count).collect(),
+        }
+    }
+
+    // The selected authors, cut to author_limit, along with the name of the author standing in for the ones cut.
+    pub(crate) fn limited_authors(&self, entry_authors: &EntryAuthors, author_limit: &AuthorLimit) -> (Vec<&Author>, Option<String>) {
+        let mut authors = self.selected_authors(entry_authors);
+        if author_limit.max_authors == 0 || authors.len() <= author_limit.max_authors {
+            return (authors, None);
+        }
+        let others = authors.len() - author_limit.max_authors;
+        authors.truncate(author_limit.max_authors);
+        let overflow_author = author_limit.overflow_author.as_ref().map(|name| name.replace("{count}", &others.to_string()));
+        (authors, overflow_author)
+    }
+
+    pub fn source_url(&self, base_url: Option<&Url>) -> Option<String> {
+        // mdbook generates these pages itself, so a chapter rendered to the same place would be overwritten.
+        let page = PostFinder::compute_url(None, &self.path)?;
+        if RESERVED_PAGES.contains(&page.as_str()) {
+            log::warn!("Not linking to {}, which would collide with mdbook's {}", self.path.display(), page);
+            return None;
+        }
+        PostFinder::compute_url(base_url, &self.path)
+    }
+}
+
+fn blame_hunk_infos(blame: &Blame) -> Vec<BlameHunkInfo> {
+    blame.iter()
+        .map(|hunk| {
+            let signature = hunk.final_signature();
+            BlameHunkInfo {
+                start_line: hunk.final_start_line(),
+                num_lines: hunk.lines_in_hunk(),
+                commit_id: hunk.final_commit_id().to_string(),
+                author_name: signature.name().unwrap_or("").to_string(),
+                author_email: signature.email().map(|email| email.to_string()),
+                date: signature.when(),
+            }
+        })
+        .collect()
+}
+
+// Returns the argument of the first {{#title ...}} directive and the markdown with directive lines removed.
+fn strip_title_directive(markdown: &str) -> (Option<String>, String) {
+    let re = Regex::new(r"(?m)^[ \t]*\{\{\s*#title\s+(.*?)\s*\}\}[ \t]*(\r?\n|$)").unwrap();
+    let title = re.captures(markdown)
+        .and_then(|captures| captures.get(1))
+        .map(|title| title.as_str().to_string());
+    (title, re.replace_all(markdown, "").to_string())
+}
+
+// When the file at path was created and last modified according to the filesystem, for chapters git can't date.
+// Filesystems that don't record when files were created use when they were modified for both.
+fn file_dates(path: &Path) -> Option<(Time, Time)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = system_time(metadata.modified().ok()?);
+    let created = metadata.created().map(system_time).unwrap_or(modified);
+    Some((created.min(modified), modified))
+}
+
+fn system_time(time: SystemTime) -> Time {
+    let date: DateTime<Utc> = time.into();
+    Time::new(date.timestamp(), 0)
+}
+
+// Splits a leading block of TOML front matter, between "+++" lines, from the rest of the markdown. Front matter that
+// isn't valid TOML is still removed, but ignored.
+fn strip_front_matter(markdown: &str) -> (Option<toml::value::Table>, String) {
+    let re = Regex::new(r"(?m)\A\+\+\+[ \t]*\n((?s:.*?))^\+\+\+[ \t]*(?:\n|\z)").unwrap();
+    let captures = match re.captures(markdown) {
+        Some(captures) => captures,
+        None => return (None, markdown.to_string()),
+    };
+    let front_matter = match toml::from_str::<toml::value::Table>(&captures[1]) {
+        Ok(front_matter) => Some(front_matter),
+        Err(error) => {
+            log::warn!("Ignoring front matter that isn't valid TOML: {}", error);
+            None
+        }
+    };
+    (front_matter, markdown[captures.get(0).expect("Captures always have a whole match").end()..].to_string())
+}
+
+// The text of the first "# " heading, if there is one.
+fn extract_title_from_markdown(markdown: &str) -> Option<String> {
+    let re = Regex::new(r"(?m)^# (.+)$").unwrap();
+    re.captures(markdown)
+        .and_then(|captures| captures.get(1))
+        .map(|heading| heading.as_str().trim().to_string())
+        .filter(|heading| !heading.is_empty())
+}
+
+// Chapter names can have inline markdown and html, like `code` or <em>emphasis</em>, but feed and list titles are
+// plain text. Anything that would start a block, like "1. " or "# ", is escaped so it stays part of the title.
+fn plain_text_title(title: &str) -> String {
+    let title = title.trim();
+    let block_start = Regex::new(r"^(?:[#>*+=-]|\d+[.)])").unwrap();
+    let title = match block_start.find(title) {
+        Some(start) => format!("{}\\{}", &title[..start.end() - 1], &title[start.end() - 1..]),
+        None => title.to_string(),
+    };
+    let mut text = String::new();
+    for event in Parser::new_ext(&title, Options::empty()) {
+        match event {
+            Event::Text(part) | Event::Code(part) => text.push_str(&part),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => (),
+        }
+    }
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// The headings whose sections contain any of changed_lines, which are (first line, number of lines) with lines
+// numbered from 1. Changes before the first heading don't belong to any section.
+fn changed_sections(markdown: &str, changed_lines: &[(usize, usize)]) -> Vec<String> {
+    let mut headings: Vec<(usize, String)> = vec![];
+    let mut heading: Option<(usize, String)> = None;
+    for (event, range) in Parser::new_ext(markdown, Options::empty()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading(_)) => {
+                let line = markdown[..range.start].matches('\n').count() + 1;
+                heading = Some((line, String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, name)) = heading.as_mut() {
+                    name.push_str(&text);
+                }
+            }
+            Event::End(Tag::Heading(_)) => headings.extend(heading.take()),
+            _ => (),
+        }
+    }
+
+    let mut sections: Vec<String> = vec![];
+    for (index, (line, name)) in headings.iter().enumerate() {
+        let end = headings.get(index + 1).map_or(usize::MAX, |(next_line, _)| *next_line);
+        let changed = changed_lines.iter().any(|(start, count)| *start < end && start + count > *line);
+        if changed && !sections.contains(name) {
+            sections.push(name.to_string());
+        }
+    }
+    sections
+}
+
+fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+// Removes the heading the chapter starts with when it's an h1 of the title, which the entry already shows. Any other
+// heading is left alone, even an h1 of the title further down or in a code block.
+fn strip_title_heading<'a>(markdown: &'a str, title: &str) -> &'a str {
+    let mut events = Parser::new_ext(markdown, Options::empty()).into_offset_iter();
+    let heading_range = match events.next() {
+        Some((Event::Start(Tag::Heading(1)), range)) => range,
+        _ => return markdown,
+    };
+    let mut heading = String::new();
+    for (event, _) in events {
+        match event {
+            Event::Text(text) | Event::Code(text) => heading.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => heading.push(' '),
+            Event::End(Tag::Heading(_)) => break,
+            _ => (),
+        }
+    }
+    if heading.split_whitespace().collect::<Vec<&str>>().join(" ") != title {
+        return markdown;
+    }
+    markdown[heading_range.end..].trim_start_matches('\n')
+}
+
+fn top_level_directory(path: &Path) -> Option<String> {
+    let mut components = path.components();
+    let first = components.next()?;
+    components.next()?;
+    Some(first.as_os_str().to_str()?.to_string())
+}
+
+// The first number_of_lines lines of reader, or all of them when it's -1, along with how many lines were longer than
+// max_line_bytes. Those are cut short with an ellipsis, without reading the rest of the line into memory.
+fn markdown_preview<R: BufRead>(mut reader: R, number_of_lines: i64, max_line_bytes: usize) -> (String, usize) {
+    let number_of_lines = match number_of_lines {
+        -1 => usize::MAX,
+        number_of_lines => usize::try_from(number_of_lines).unwrap_or(0),
+    };
+    let mut lines: Vec<String> = Vec::new();
+    let mut shortened_lines = 0;
+    let mut ends_with_newline = false;
+    while lines.len() < number_of_lines {
+        let (line, shortened, newline) = match read_capped_line(&mut reader, max_line_bytes) {
+            Some(line) => line,
+            None => break,
+        };
+        if shortened {
+            shortened_lines += 1;
+        }
+        lines.push(line);
+        ends_with_newline = newline;
+    }
+    let mut markdown_content = lines.join("\n");
+    // Whole chapters keep their final newline, the same as they're read.
+    if number_of_lines == usize::MAX && ends_with_newline {
+        markdown_content.push('\n');
+    }
+    (markdown_content, shortened_lines)
+}
+
+// Reads a line, without its line ending, keeping at most max_bytes of it. Gives the line, whether it was cut short, and
+// whether it ended with a newline, or None at the end of reader.
+fn read_capped_line<R: BufRead>(reader: &mut R, max_bytes: usize) -> Option<(String, bool, bool)> {
+    let mut line: Vec<u8> = Vec::new();
+    let mut shortened = false;
+    let mut newline = false;
+    let mut read_anything = false;
+    loop {
+        let buffer = reader.fill_buf().ok()?;
+        if buffer.is_empty() {
+            break;
+        }
+        read_anything = true;
+        let end = buffer.iter().position(|byte| *byte == b'\n');
+        let chunk = &buffer[..end.unwrap_or(buffer.len())];
+        let room = max_bytes.saturating_sub(line.len());
+        if chunk.len() > room {
+            shortened = true;
+        }
+        line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        let consumed = chunk.len() + usize::from(end.is_some());
+        reader.consume(consumed);
+        if end.is_some() {
+            newline = true;
+            break;
+        }
+    }
+    if !read_anything {
+        return None;
+    }
+    if line.last() == Some(&b'\r') && !shortened {
+        line.pop();
+    }
+    let mut line = match String::from_utf8(line) {
+        Ok(line) => line,
+        // Cutting a line short can split a character, which is left out.
+        Err(error) => {
+            let valid_up_to = error.utf8_error().valid_up_to();
+            let mut bytes = error.into_bytes();
+            bytes.truncate(valid_up_to);
+            String::from_utf8(bytes).ok()?
+        }
+    };
+    if shortened {
+        line.push('…');
+    }
+    Some((line, shortened, newline))
+}
+
+pub(crate) fn render_html(markdown_content: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    let parser = Parser::new_ext(markdown_content, options);
+
+    let mut content_string = String::new();
+    html::push_html(&mut content_string, parser);
+    content_string
+}
+
+fn first_image(markdown_content: &str) -> Option<String> {
+    Parser::new_ext(markdown_content, Options::empty())
+        .filter_map(|event| match event {
+            Event::Start(Tag::Image(_, source, _)) => Some(source.to_string()),
+            _ => None,
+        })
+        .find(|source| {
+            let lowercased = source.to_lowercase();
+            !lowercased.starts_with("data:") && !lowercased.split(['?', '#']).next().unwrap_or("").ends_with(".svg")
+        })
+}
+
+fn render_markdown_for_counting(markdown_content: &str, count_code_blocks: bool) -> String {
+    let mut in_code_block = false;
+    let parser = Parser::new_ext(markdown_content, Options::empty())
+        .filter(|event| {
+            match event {
+                Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+                Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+                _ => (),
+            }
+            count_code_blocks || !in_code_block
+        });
+
+    let mut content_string = String::new();
+    html::push_html(&mut content_string, parser);
+    content_string
+}
+
+fn log_skip_summary(skipped: &[(String, SkipReason)]) {
+    for line in skip_summary(skipped) {
+        log::info!("{}", line);
+    }
+}
+
+// The ids of the posts for every chapter of book with a source file, including the ones searches leave out, like
+// chapters that are too old or weren't changed in the dates or commits being blamed. Those still exist, so only entries
+// whose ids aren't among these were deleted.
+pub fn chapter_ids(book: &Book, content_path: &Path) -> HashSet<String> {
+    book.iter()
+        .filter_map(|item| match item {
+            BookItem::Chapter(chapter) => Some(chapter),
+            _ => None,
+        })
+        .filter(|chapter| chapter.source_path.as_ref().is_none_or(|source_path| content_path.join(source_path).is_file()))
+        .filter_map(|chapter| Some(chapter.path.as_ref()?.to_str()?.to_string()))
+        .collect()
+}
+
+// A line for each reason chapters were skipped for, listing the chapters.
+pub fn skip_summary(skipped: &[(String, SkipReason)]) -> Vec<String> {
+    let mut summary = vec![];
+    let names = |wanted: fn(&SkipReason) -> bool| -> Vec<&str> {
+        skipped.iter()
+            .filter(|(_, reason)| wanted(reason))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    };
+
+    let drafts = names(|reason| matches!(reason, SkipReason::Draft));
+    if !drafts.is_empty() {
+        summary.push(format!("Skipped {} draft chapter(s): {}", drafts.len(), drafts.join(", ")));
+    }
+    let excluded = names(|reason| matches!(reason, SkipReason::Excluded));
+    if !excluded.is_empty() {
+        summary.push(format!("Skipped {} excluded chapter(s): {}", excluded.len(), excluded.join(", ")));
+    }
+    let no_history = names(|reason| matches!(reason, SkipReason::NoHistory));
+    if !no_history.is_empty() {
+        summary.push(format!("Skipped {} chapter(s) without git history: {}", no_history.len(), no_history.join(", ")));
+    }
+    let synthesized = names(|reason| matches!(reason, SkipReason::Synthesized));
+    if !synthesized.is_empty() {
+        summary.push(format!("Skipped {} chapter(s) without a source file: {}", synthesized.len(), synthesized.join(", ")));
+    }
+    summary
+}
+
+fn count_words_in_html(html: &str) -> u32 {
+    let re = Regex::new(r"<[^>]+>").unwrap();
+    re.replace_all(html, " ").split_whitespace().count() as u32
+}
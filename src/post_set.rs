@@ -0,0 +1,114 @@
+use std::convert::TryFrom;
+use git2::Time;
+use regex::Regex;
+use crate::post_finder::{sort_posts, Post, SortDirection, SortField};
+
+// Posts to pick the published ones from. Every operation gives a new set, so they can be chained, like
+// PostSet::new(posts).sorted_by_updated().within_days(30, now).take_recent(10).
+#[derive(Clone, Debug, Default)]
+pub struct PostSet {
+    posts: Vec<Post>,
+}
+
+impl PostSet {
+    pub fn new(posts: Vec<Post>) -> PostSet {
+        PostSet { posts }
+    }
+
+    // Most recently updated first. Posts updated at the same time stay in the same order.
+    pub fn sorted_by_updated(mut self) -> PostSet {
+        sort_posts(&mut self.posts, SortField::LastModified, SortDirection::Descending);
+        self
+    }
+
+    // Most recently published first. Posts published at the same time stay in the same order.
+    pub fn sorted_by_published(mut self) -> PostSet {
+        sort_posts(&mut self.posts, SortField::Created, SortDirection::Descending);
+        self
+    }
+
+    // The first count posts, which are the most recent ones once the set is sorted.
+    pub fn take_recent(mut self, count: usize) -> PostSet {
+        self.posts.truncate(count);
+        self
+    }
+
+    // Posts updated no more than days before reference_time.
+    pub fn within_days(mut self, days: u64, reference_time: Time) -> PostSet {
+        let oldest = reference_time.seconds().saturating_sub(i64::try_from(days.saturating_mul(86_400)).unwrap_or(i64::MAX));
+        self.posts.retain(|post| post.last_modified_date.seconds() >= oldest);
+        self
+    }
+
+    // Posts whose path, relative to the src directory, matches glob. `*` matches within a directory, `**` matches
+    // across directories and `?` matches a single character, so "guide/**" is every chapter in guide.
+    pub fn filter_paths(mut self, glob: &str) -> PostSet {
+        let regex = glob_regex(glob);
+        self.posts.retain(|post| post.path.to_str().is_some_and(|path| regex.is_match(&path.replace('\\', "/"))));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.posts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.posts.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Post> {
+        self.posts.iter()
+    }
+
+    pub fn into_vec(self) -> Vec<Post> {
+        self.posts
+    }
+}
+
+impl From<Vec<Post>> for PostSet {
+    fn from(posts: Vec<Post>) -> Self {
+        PostSet::new(posts)
+    }
+}
+
+impl IntoIterator for PostSet {
+    type Item = Post;
+    type IntoIter = std::vec::IntoIter<Post>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.posts.into_iter()
+    }
+}
+
+// Sorted posts are most recent first, so the ones to publish are at the front. 0 or less keeps every post, the same
+// as target_number_of_entries.
+pub(crate) fn most_recent<T>(posts: &[T], target_entries: i64) -> &[T] {
+    match usize::try_from(target_entries) {
+        Ok(target_entries) if target_entries > 0 => &posts[..posts.len().min(target_entries)],
+        _ => posts,
+    }
+}
+
+fn glob_regex(glob: &str) -> Regex {
+    let mut pattern = String::from("^");
+    let mut characters = glob.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '*' if characters.peek() == Some(&'*') => {
+                characters.next();
+                // "**/" also matches no directories at all.
+                if characters.peek() == Some(&'/') {
+                    characters.next();
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            character => pattern.push_str(&regex::escape(&character.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).expect("Escaped globs are valid regexes")
+}
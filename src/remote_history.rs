@@ -0,0 +1,239 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use chrono::DateTime;
+use git2::{Repository, Time};
+use serde::{Deserialize, Serialize};
+use crate::post_finder::Author;
+
+// Where a book's repository is hosted.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemoteProvider {
+    GitHub,
+}
+
+impl FromStr for RemoteProvider {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "github" => Ok(RemoteProvider::GitHub),
+            _ => Err(format!("{}. Expected \"github\".", value)),
+        }
+    }
+}
+
+// Where to look up the history of chapters that a shallow clone doesn't have.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteFallback {
+    pub provider: RemoteProvider,
+    // The repository on the provider, like "me/book".
+    pub repo: String,
+    // The environment variable with the token to authenticate with, if any.
+    pub token_env: Option<String>,
+}
+
+// Fetches urls for the providers, so other http libraries (or stubs) can be used.
+pub trait HttpClient {
+    fn get(&self, url: &str, headers: &[(&str, String)]) -> Result<String, String>;
+}
+
+struct UreqClient;
+
+#[cfg(feature = "remote-fallback")]
+impl HttpClient for UreqClient {
+    fn get(&self, url: &str, headers: &[(&str, String)]) -> Result<String, String> {
+        let request = headers.iter()
+            .fold(ureq::get(url), |request, (name, value)| request.set(name, value));
+        request.call()
+            .map_err(|error| error.to_string())?
+            .into_string()
+            .map_err(|error| error.to_string())
+    }
+}
+
+#[cfg(not(feature = "remote-fallback"))]
+impl HttpClient for UreqClient {
+    fn get(&self, _url: &str, _headers: &[(&str, String)]) -> Result<String, String> {
+        Err("looking up history requires the remote-fallback feature".to_string())
+    }
+}
+
+// The history of a path according to its provider. Times are stored as seconds and offsets so they can be cached.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct PathHistory {
+    first_commit: (i64, i32),
+    last_commit: (i64, i32),
+    // Everyone who committed to the path, with their number of commits.
+    pub(crate) authors: Vec<(Author, usize)>,
+    // The full shas of the first and last commits, which caches from older versions don't have.
+    #[serde(default)]
+    pub(crate) first_commit_id: Option<String>,
+    #[serde(default)]
+    pub(crate) last_commit_id: Option<String>,
+    // How many commits changed the path.
+    #[serde(default)]
+    pub(crate) revisions: u32,
+    // When each author first and last changed the path, and who changed it last. Only the local history has them.
+    #[serde(skip)]
+    pub(crate) author_dates: HashMap<Author, (Time, Time)>,
+    #[serde(skip)]
+    pub(crate) last_author: Option<Author>,
+}
+
+impl PathHistory {
+    pub(crate) fn new(first_commit: Time, last_commit: Time, authors: Vec<(Author, usize)>, revisions: u32) -> PathHistory {
+        PathHistory {
+            first_commit: (first_commit.seconds(), first_commit.offset_minutes()),
+            last_commit: (last_commit.seconds(), last_commit.offset_minutes()),
+            authors,
+            first_commit_id: None,
+            last_commit_id: None,
+            revisions,
+            author_dates: HashMap::new(),
+            last_author: None,
+        }
+    }
+
+    pub(crate) fn with_commit_ids(mut self, first_commit_id: String, last_commit_id: String) -> PathHistory {
+        self.first_commit_id = Some(first_commit_id);
+        self.last_commit_id = Some(last_commit_id);
+        self
+    }
+
+    pub(crate) fn first_commit(&self) -> Time {
+        Time::new(self.first_commit.0, self.first_commit.1)
+    }
+
+    pub(crate) fn last_commit(&self) -> Time {
+        Time::new(self.last_commit.0, self.last_commit.1)
+    }
+}
+
+// The most pages of commits fetched for a single path.
+const MAXIMUM_PAGES: usize = 20;
+const COMMITS_PER_PAGE: usize = 100;
+
+// Looks up the history of paths from their provider, caching them next to the repository by HEAD.
+pub struct RemoteHistory {
+    fallback: RemoteFallback,
+    client: Box<dyn HttpClient>,
+    // Loaded from disk the first time a path is looked up.
+    cache: RefCell<Option<HashMap<String, PathHistory>>>,
+}
+
+impl RemoteHistory {
+    pub fn new(fallback: RemoteFallback) -> RemoteHistory {
+        RemoteHistory::with_client(fallback, Box::new(UreqClient))
+    }
+
+    pub fn with_client(fallback: RemoteFallback, client: Box<dyn HttpClient>) -> RemoteHistory {
+        RemoteHistory { fallback, client, cache: RefCell::new(None) }
+    }
+
+    // The history of path, relative to the root of repo. Failures are logged and give None, so posts fall back to
+    // the local history.
+    pub(crate) fn path_history(&self, repo: &Repository, path: &Path) -> Option<PathHistory> {
+        let head = repo.head().ok()?.target()?;
+        let path = path.to_str()?.replace('\\', "/");
+        let key = format!("{}:{}", head, path);
+        let cache_path = cache_path(repo);
+
+        let mut cache = self.cache.borrow_mut();
+        let cache = cache.get_or_insert_with(|| {
+            fs::read_to_string(&cache_path).ok()
+                .and_then(|contents| serde_json::from_str(&contents).ok())
+                .unwrap_or_default()
+        });
+        if let Some(history) = cache.get(&key) {
+            return Some(history.clone());
+        }
+
+        let history = match self.fetch(&path) {
+            Ok(history) => history,
+            Err(error) => {
+                log::warn!("Unable to look up the history of {} on {}, using the shallow history instead: {}", path, self.fallback.repo, error);
+                return None;
+            }
+        };
+        // Only the current HEAD's histories are kept, so the cache doesn't grow with every commit.
+        let head_prefix = format!("{}:", head);
+        cache.retain(|key, _| key.starts_with(&head_prefix));
+        cache.insert(key, history.clone());
+        if let Err(error) = serde_json::to_string(&*cache).map_err(|error| error.to_string())
+            .and_then(|contents| fs::write(&cache_path, contents).map_err(|error| error.to_string())) {
+            log::warn!("Unable to cache remote history at {}: {}", cache_path.display(), error);
+        }
+        Some(history)
+    }
+
+    fn fetch(&self, path: &str) -> Result<PathHistory, String> {
+        match self.fallback.provider {
+            RemoteProvider::GitHub => self.fetch_github(path),
+        }
+    }
+
+    fn fetch_github(&self, path: &str) -> Result<PathHistory, String> {
+        let mut headers = vec![("Accept", "application/vnd.github+json".to_string())];
+        if let Some(token) = self.fallback.token_env.as_ref().and_then(|token_env| std::env::var(token_env).ok()) {
+            headers.push(("Authorization", format!("Bearer {}", token)));
+        }
+
+        // Commits are listed newest first.
+        let mut commits: Vec<(Time, Author, Option<String>)> = vec![];
+        for page in 1..=MAXIMUM_PAGES {
+            let url = format!(
+                "https://api.github.com/repos/{}/commits?path={}&per_page={}&page={}",
+                self.fallback.repo, url::form_urlencoded::byte_serialize(path.as_bytes()).collect::<String>(), COMMITS_PER_PAGE, page
+            );
+            let response: serde_json::Value = serde_json::from_str(&self.client.get(&url, &headers)?)
+                .map_err(|error| error.to_string())?;
+            let page_commits = response.as_array().ok_or("Expected a list of commits")?;
+            for commit in page_commits {
+                let author = &commit["commit"]["author"];
+                let date = author["date"].as_str()
+                    .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+                    .ok_or("Expected commits to have dates")?;
+                let time = Time::new(date.timestamp(), date.offset().local_minus_utc() / 60);
+                let commit_author = Author {
+                    name: author["name"].as_str().unwrap_or("").to_string(),
+                    email: author["email"].as_str().map(|email| email.to_string()),
+                };
+                commits.push((time, commit_author, commit["sha"].as_str().map(|sha| sha.to_string())));
+            }
+            if page_commits.len() < COMMITS_PER_PAGE {
+                break;
+            }
+        }
+
+        let (last_commit, _, last_commit_id) = commits.first().cloned().ok_or("No commits found")?;
+        let (first_commit, _, first_commit_id) = commits.last().cloned().ok_or("No commits found")?;
+        let revisions = u32::try_from(commits.len()).unwrap_or(u32::MAX);
+        let mut authors: Vec<(Author, usize)> = vec![];
+        for (_, author, _) in commits.into_iter().filter(|(_, author, _)| !author.name.is_empty()) {
+            match authors.iter_mut().find(|(existing, _)| *existing == author) {
+                Some((_, count)) => *count += 1,
+                None => authors.push((author, 1)),
+            }
+        }
+        Ok(PathHistory {
+            first_commit: (first_commit.seconds(), first_commit.offset_minutes()),
+            last_commit: (last_commit.seconds(), last_commit.offset_minutes()),
+            authors,
+            first_commit_id,
+            last_commit_id,
+            revisions,
+            author_dates: HashMap::new(),
+            last_author: None,
+        })
+    }
+}
+
+// The path a repository's remote history is cached at.
+fn cache_path(repo: &Repository) -> PathBuf {
+    repo.path().join("mdbook-git-atom-remote-history.json")
+}
@@ -1,114 +1,687 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use git2::Time;
 use chrono::FixedOffset;
 use mdbook::book::Book;
 use mdbook::BookItem;
 use mdbook::errors::Error;
 use mdbook::preprocess::{Preprocessor, PreprocessorContext};
+use pulldown_cmark::{Event, Parser, Tag};
 use regex::{Captures, Regex};
-use crate::post_finder::{Post, PostFinder};
+use url::Url;
+use crate::errors::{ConfigError, SearchError};
+use crate::library_helpers::{log_progress, parse_author_limit, parse_blame_config, parse_date_overrides, parse_entry_authors, parse_excluded_paths, parse_invalid_utf8, parse_reading_speed, parse_remote_fallback, parse_renderers, parse_retry_open_attempts, parse_section, parse_sort, parse_translations, read_feed_env, write_if_changed};
+use crate::post_finder::{DateOverride, render_html, Author, AuthorLimit, AuthorStats, BlameConfig, BookStatistics, EntryAuthors, HistoryProvider, InvalidUtf8, Post, PostFinder, SortDirection, SortField};
+use crate::post_set::most_recent;
+use crate::remote_history::{RemoteFallback, RemoteHistory};
 
-pub struct UpdatedProcessor;
+// Reads its options from the book's [preprocessor.git-updated] section and the history from the book's repository,
+// unless they're given to it, like when a custom mdbook driver or a test makes the preprocessor.
+#[derive(Default)]
+pub struct UpdatedProcessor {
+    config: Option<UpdatedConfig>,
+    history_provider: Option<Box<dyn HistoryProvider>>,
+}
+
+const DEFAULT_TEMPLATE: &str = "- [{title}](/{url}) ({last_modified})";
+const DEFAULT_CONTRIBUTORS_TEMPLATE: &str = "- {name}";
+
+// How {{#recently_updated}} lists are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RecentlyUpdatedStyle {
+    // A markdown list, with each entry formatted by template.
+    List,
+    // An html <aside class="recently-updated">, for themes that style it.
+    Aside,
+}
+
+impl FromStr for RecentlyUpdatedStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "list" => Ok(RecentlyUpdatedStyle::List),
+            "aside" => Ok(RecentlyUpdatedStyle::Aside),
+            _ => Err(format!("{}. Expected \"list\" or \"aside\".", value)),
+        }
+    }
+}
+
+// The timezone dates in lists and directives are shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum DisplayTimezone {
+    // The offset each commit was made at, so dates are the ones their authors saw.
+    #[default]
+    CommitOffset,
+    Fixed(FixedOffset),
+    #[cfg(feature = "timezones")]
+    Named(chrono_tz::Tz),
+}
 
-struct UpdatedConfig {
-    content_path: PathBuf,
-    root_path: PathBuf,
+impl FromStr for DisplayTimezone {
+    type Err = String;
+
+    // Offsets like "+09:00", "UTC", or names like "Asia/Tokyo" with the timezones feature.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "UTC" || value == "Z" {
+            return Ok(DisplayTimezone::Fixed(FixedOffset::east(0)));
+        }
+        let offset_regex = Regex::new(r"^([+-])(\d{2}):(\d{2})$").unwrap();
+        if let Some(captures) = offset_regex.captures(value) {
+            let sign = if &captures[1] == "-" { -1 } else { 1 };
+            let seconds = captures[2].parse::<i32>().unwrap_or(0) * 3600 + captures[3].parse::<i32>().unwrap_or(0) * 60;
+            return FixedOffset::east_opt(sign * seconds)
+                .map(DisplayTimezone::Fixed)
+                .ok_or_else(|| format!("{}. Offsets have to be less than a day.", value));
+        }
+        #[cfg(feature = "timezones")]
+        if let Ok(timezone) = value.parse::<chrono_tz::Tz>() {
+            return Ok(DisplayTimezone::Named(timezone));
+        }
+        if cfg!(feature = "timezones") {
+            Err(format!("{}. Expected an offset like \"+09:00\" or a timezone like \"Asia/Tokyo\".", value))
+        } else {
+            Err(format!("{}. Expected an offset like \"+09:00\". Names like \"Asia/Tokyo\" need the timezones feature.", value))
+        }
+    }
+}
+
+// The options of the recently updated lists and other directives, usually read from the book's config.
+pub struct UpdatedConfig {
+    pub(crate) content_path: PathBuf,
+    pub(crate) root_path: PathBuf,
     // Target number of entries in the atom feed to create. Defaults to 10.
-    // Set this to 0 to get the old behavior where minimum_number_of_commits is paid attention to.
-    // This basically overrides minimum_number_of_commits when it's a positive number.
-    // We'll search as far back as necessary to create the target amount of entries.
-    target_number_of_entries: i64,
+    // Set this to 0 to include every post.
+    pub(crate) target_number_of_entries: i64,
+    // Whether chapters without a source file (e.g. created by another preprocessor) are included, dated by HEAD.
+    // Defaults to true.
+    pub(crate) include_synthesized_chapters: bool,
+    // Chapters nested deeper than this many levels are listed as their ancestor at this depth, the same as the atom
+    // feed's rollup_depth. 0 disables this. Defaults to 0.
+    pub(crate) rollup_depth: usize,
+    // Whether a "# " heading of the title that a chapter starts with is left out of its content, the same as the atom
+    // feed's strip_h1_from_content. Defaults to true.
+    pub(crate) strip_h1_from_content: bool,
+    // How chapters are blamed, set with the same options as the atom feed's, like first_parent and
+    // max_blame_depth.
+    pub(crate) blame_config: BlameConfig,
+    // Format string used for each entry in the list. Supports {title}, {url}, {last_modified}, {word_count},
+    // {reading_time}, {authors}, {section}, {changed_sections}, {churn} and {revisions}.
+    pub(crate) template: String,
+    // Whether lists are markdown or an html aside. Defaults to a markdown list.
+    pub(crate) recently_updated_style: RecentlyUpdatedStyle,
+    // The level of the heading lists with a title="..." get, from 1 to 6. Defaults to 2.
+    pub(crate) heading_level: u32,
+    // The timezone dates are shown in, as an offset like "+09:00" or, with the timezones feature, a name like
+    // "Asia/Tokyo". Defaults to UTC.
+    pub(crate) timezone: DisplayTimezone,
+    // Text used for {{#last_modified}} and {{#created_date}} in chapters without git history.
+    // Defaults to "(unpublished)".
+    pub(crate) missing_date_text: String,
+    // Which authors {authors} lists, the same as the atom feed's entry_authors. Defaults to "all".
+    pub(crate) entry_authors: EntryAuthors,
+    // The most authors {authors} lists and the name standing in for the rest, the same as the atom feed's
+    // max_entry_authors and overflow_author. Defaults to listing all of them.
+    pub(crate) author_limit: AuthorLimit,
+    // Reading speed used to estimate {reading_time}, in words per minute. Defaults to 200.
+    pub(crate) words_per_minute: u32,
+    // Whether words in code blocks count towards {word_count} and {reading_time}. Defaults to true.
+    pub(crate) count_code_blocks: bool,
+    // What posts are sorted by: "last_modified", "created" or "title". Defaults to "last_modified".
+    pub(crate) sort_field: SortField,
+    // "ascending" or "descending". Defaults to "descending".
+    pub(crate) sort_direction: SortDirection,
+    // Where to write the recently updated list as an html fragment, relative to the book root. Not written by default.
+    pub(crate) fragment_output: Option<PathBuf>,
+    // Languages whose chapters are in a top-level directory of the same name. Recently updated lists in those
+    // directories only list chapters in the same language. Defaults to none.
+    pub(crate) translations: Vec<String>,
+    // What to do with chapters that aren't valid UTF-8, the same as the atom feed's invalid_utf8. Defaults to "lossy".
+    pub(crate) invalid_utf8: InvalidUtf8,
+    // Chapters that never become posts, the same as the atom feed's exclude and exclude_defaults.
+    pub(crate) excluded_paths: Vec<PathBuf>,
+    // How many times opening the repository is tried, the same as the atom feed's retry_open_attempts. Defaults to 1.
+    pub(crate) retry_open_attempts: u32,
+    // Only chapters in this part of the book, or top-level directory when it has no parts, are listed.
+    // Defaults to every chapter.
+    pub(crate) section: Option<String>,
+    // Whether {changed_sections} is filled in with the headings of the sections each chapter's latest commit changed.
+    // Defaults to false.
+    pub(crate) changed_sections: bool,
+    // Where to look up the history of chapters older than a shallow clone.
+    pub(crate) remote_fallback: Option<RemoteFallback>,
+    // The notes ref with dates that override when chapters were created, like "refs/notes/publish-dates".
+    pub(crate) notes_ref: Option<String>,
+    // Dates that override the ones from git, read from dates_file. Defaults to those in dates.toml, if it exists.
+    pub(crate) date_overrides: HashMap<PathBuf, DateOverride>,
+    // Whether book statistics include commits on every local branch, not just the ones reachable from HEAD.
+    // Defaults to false.
+    pub(crate) consider_all_branches: bool,
+    // The renderers directives are expanded for. Other renderers have them stripped. Defaults to just html.
+    pub(crate) renderers: Vec<String>,
+    // The url {{#feed_url}} is replaced with, from the file the atom preprocessor writes. None until it's run.
+    pub(crate) feed_url: Option<String>,
 }
 
 impl UpdatedConfig {
-    fn from_book_config(ctx: &PreprocessorContext, name: &str) -> Option<UpdatedConfig> {
-        let section_config = ctx.config.get_preprocessor(name)?;
+    pub fn from_book_config(ctx: &PreprocessorContext, name: &str) -> Result<UpdatedConfig, ConfigError> {
+        let section_config = ctx.config.get_preprocessor(name)
+            .ok_or_else(|| ConfigError::MissingSection(name.to_string()))?;
 
         let mut target_number_of_entries: &i64 = &10;
         if let Some(toml::Value::Integer(target_entries)) = section_config.get("target_number_of_entries") {
             if (*target_entries) < -1 {
-                panic!("Invalid target number of entries provided: {}. Expected 0 or a positive number.", target_entries);
+                return Err(ConfigError::invalid_value("target_number_of_entries", format!("{}. Expected 0 or a positive number.", target_entries)));
             }
             target_number_of_entries = target_entries;
         }
+        let mut include_synthesized_chapters = true;
+        if let Some(toml::Value::Boolean(include_synthesized)) = section_config.get("include_synthesized_chapters") {
+            include_synthesized_chapters = *include_synthesized;
+        }
+        let mut rollup_depth = 0;
+        if let Some(toml::Value::Integer(depth)) = section_config.get("rollup_depth") {
+            if (*depth) < 0 {
+                return Err(ConfigError::invalid_value("rollup_depth", format!("{}. Expected 0 or a positive number.", depth)));
+            }
+            rollup_depth = *depth as usize;
+        }
+        let mut strip_h1_from_content = true;
+        if let Some(toml::Value::Boolean(strip_h1)) = section_config.get("strip_h1_from_content") {
+            strip_h1_from_content = *strip_h1;
+        }
+        let blame_config = parse_blame_config(section_config)?;
+        let mut template = DEFAULT_TEMPLATE;
+        if let Some(toml::Value::String(configured_template)) = section_config.get("template") {
+            template = configured_template.as_str();
+        }
+        let mut recently_updated_style = RecentlyUpdatedStyle::List;
+        if let Some(toml::Value::String(configured_style)) = section_config.get("recently_updated_style") {
+            recently_updated_style = configured_style.parse()
+                .map_err(|message| ConfigError::invalid_value("recently_updated_style", message))?;
+        }
+        let heading_level = match section_config.get("heading_level") {
+            None => 2,
+            Some(toml::Value::Integer(level)) => u32::try_from(*level).ok()
+                .filter(|level| (1..=6).contains(level))
+                .ok_or_else(|| ConfigError::invalid_value("heading_level", format!("{}. Expected a number from 1 to 6.", level)))?,
+            Some(value) => return Err(ConfigError::invalid_value("heading_level", format!("{}. Expected a number from 1 to 6.", value))),
+        };
+        let mut timezone = DisplayTimezone::default();
+        if let Some(toml::Value::String(configured_timezone)) = section_config.get("timezone") {
+            timezone = configured_timezone.parse()
+                .map_err(|message| ConfigError::invalid_value("timezone", message))?;
+        }
+        let mut missing_date_text = "(unpublished)";
+        if let Some(toml::Value::String(text)) = section_config.get("missing_date_text") {
+            missing_date_text = text.as_str();
+        }
+        let entry_authors = parse_entry_authors(section_config)?;
+        let author_limit = parse_author_limit(section_config)?;
+        let (words_per_minute, count_code_blocks) = parse_reading_speed(section_config)?;
+        let (sort_field, sort_direction) = parse_sort(section_config)?;
+        let translations = parse_translations(section_config)?;
+        let invalid_utf8 = parse_invalid_utf8(section_config)?;
+        let excluded_paths = parse_excluded_paths(section_config)?;
+        let retry_open_attempts = parse_retry_open_attempts(section_config)?;
+        let section = parse_section(section_config);
+        let remote_fallback = parse_remote_fallback(section_config)?;
+        let date_overrides = parse_date_overrides(section_config, &ctx.root)?;
+        let notes_ref = match section_config.get("notes_ref") {
+            Some(toml::Value::String(notes_ref)) => Some(notes_ref.to_string()),
+            _ => None,
+        };
+        let mut changed_sections = false;
+        if let Some(toml::Value::Boolean(configured_changed_sections)) = section_config.get("changed_sections") {
+            changed_sections = *configured_changed_sections;
+        }
+        let renderers = parse_renderers(section_config)?;
+        let mut consider_all_branches = false;
+        if let Some(toml::Value::Boolean(configured_consider_all_branches)) = section_config.get("consider_all_branches") {
+            consider_all_branches = *configured_consider_all_branches;
+        }
+        let mut fragment_output = None;
+        if let Some(toml::Value::String(path)) = section_config.get("fragment_output") {
+            fragment_output = Some(ctx.root.join(path));
+        }
 
-        Some(UpdatedConfig {
+        Ok(UpdatedConfig {
             content_path: ctx.config.book.src.to_path_buf(),
             root_path: ctx.root.to_path_buf(),
             target_number_of_entries: *target_number_of_entries,
+            include_synthesized_chapters,
+            rollup_depth,
+            strip_h1_from_content,
+            blame_config,
+            template: template.to_string(),
+            recently_updated_style,
+            heading_level,
+            timezone,
+            missing_date_text: missing_date_text.to_string(),
+            entry_authors,
+            author_limit,
+            words_per_minute,
+            count_code_blocks,
+            sort_field,
+            sort_direction,
+            fragment_output,
+            translations,
+            invalid_utf8,
+            excluded_paths,
+            retry_open_attempts,
+            section,
+            changed_sections,
+            remote_fallback,
+            notes_ref,
+            date_overrides,
+            consider_all_branches,
+            renderers,
+            feed_url: read_feed_env(&ctx.root),
         })
     }
 }
 
+impl UpdatedConfig {
+    pub(crate) fn post_finder(&self) -> PostFinder {
+        PostFinder::new(&self.root_path)
+            .with_retry_open_attempts(self.retry_open_attempts)
+            .with_synthesized_chapters(self.include_synthesized_chapters)
+            .with_rollup_depth(self.rollup_depth)
+            .with_reading_speed(self.words_per_minute, self.count_code_blocks)
+            .with_strip_h1_from_content(self.strip_h1_from_content)
+            .with_blame_config(self.blame_config)
+            .with_invalid_utf8(self.invalid_utf8)
+            .with_excluded_paths(self.excluded_paths.clone())
+            .with_changed_sections(self.changed_sections)
+            .with_remote_history(self.remote_fallback.clone().map(RemoteHistory::new))
+            .with_notes_ref(self.notes_ref.clone())
+            .with_date_overrides(self.date_overrides.clone())
+            .with_all_branches(self.consider_all_branches)
+    }
+}
+
 impl Preprocessor for UpdatedProcessor {
     fn name(&self) -> &str {
         "git-updated"
     }
 
     fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book, Error> {
-        let config = UpdatedConfig::from_book_config(&ctx, self.name()).expect("Create recently updated configuration");
-
-        let post_finder = PostFinder::new(config.root_path.to_str().expect("Create PostFinder"));
-        let posts = post_finder.search(&book, &config.content_path, None, config.target_number_of_entries);
+        let book_config;
+        let config = match self.config.as_ref() {
+            Some(config) => config,
+            None => {
+                book_config = UpdatedConfig::from_book_config(ctx, self.name())?;
+                &book_config
+            }
+        };
+        if !config.renderers.contains(&ctx.renderer) {
+            self.strip_directives(&mut book);
+            return Ok(book);
+        }
 
-        book.for_each_mut(|item| {
-            if let BookItem::Chapter(chapter) = item {
-                chapter.content = self.process_chapter(&chapter.content, &posts);
+        let post_finder;
+        let history: &dyn HistoryProvider = match self.history_provider.as_deref() {
+            Some(history_provider) => history_provider,
+            None => {
+                post_finder = config.post_finder();
+                &post_finder
             }
-        });
+        };
+        history.open()?;
+        // Every chapter needs its own dates, so search all of them and only window the recently updated list.
+        let all_posts = match history.search_with_progress(&book, &config.content_path, None, 0, (config.sort_field, config.sort_direction), &log_progress) {
+            Ok(all_posts) => all_posts,
+            Err(SearchError::NoHistory) => {
+                log::warn!("No git history found, recently updated lists will be empty");
+                vec![]
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let statistics = history.statistics_for_posts(&all_posts);
+        let author_statistics = history.author_statistics(&book, &config.content_path);
+        self.process_book(&mut book, &all_posts, (&statistics, &author_statistics), config)?;
 
         Ok(book)
     }
 
-    fn supports_renderer(&self, renderer: &str) -> bool {
-        renderer == "html"
+    // Directives are stripped for renderers that aren't in renderers, which needs the context, so run checks it.
+    fn supports_renderer(&self, _renderer: &str) -> bool {
+        true
     }
 }
 
 impl UpdatedProcessor {
-    fn process_chapter(&self, content: &str, posts: &Vec<Post>) -> String {
-        // let regex = Regex::new(r"^(?P<indent>.*)\{\{#recently_updated}}").unwrap();
-        let regex = Regex::new(r"\{\{#recently_updated}}").unwrap();
+    pub fn new() -> UpdatedProcessor {
+        UpdatedProcessor::default()
+    }
 
-        let captures: Vec<Captures> = regex.captures_iter(&content).collect();
+    // Used instead of the book's [preprocessor.git-updated] section.
+    pub fn with_config(mut self, config: UpdatedConfig) -> UpdatedProcessor {
+        self.config = Some(config);
+        self
+    }
 
-        let mut processed_content = String::new();
+    // Used instead of searching the book's repository for posts.
+    pub fn with_post_finder(mut self, history_provider: Box<dyn HistoryProvider>) -> UpdatedProcessor {
+        self.history_provider = Some(history_provider);
+        self
+    }
 
-        let mut last_endpoint: usize = 0;
+    // Replaces the directives in every chapter, given every post in the book sorted newest first.
+    // Also writes the html fragment, when one is configured.
+    pub(crate) fn process_book(&self, book: &mut Book, all_posts: &[Post], statistics: (&BookStatistics, &HashMap<Author, AuthorStats>), config: &UpdatedConfig) -> io::Result<()> {
+        let book_posts: Vec<&Post> = all_posts.iter()
+            .filter(|post| config.section.is_none() || post.section == config.section)
+            .collect();
+        let post_map: HashMap<PathBuf, &Post> = all_posts.iter()
+            .map(|post| (post.path.to_path_buf(), post))
+            .collect();
 
-        for capture in captures {
-            let full_match = capture.get(0).unwrap();
+        book.for_each_mut(|item| {
+            if let BookItem::Chapter(chapter) = item {
+                let language = chapter.path.as_ref()
+                    .and_then(|path| config.translations.iter().find(|language| path.starts_with(language)));
+                let chapter_posts: Vec<&Post> = match language {
+                    Some(language) => book_posts.iter().filter(|post| post.path.starts_with(language)).copied().collect(),
+                    None => book_posts.clone(),
+                };
+                chapter.content = self.process_chapter(&chapter.content, &chapter_posts, chapter.path.as_deref(), &post_map, statistics, config);
+            }
+        });
 
-            // if let Some(indentation) = capture.name("indent") {
-                processed_content.push_str(&content[last_endpoint..full_match.start()]);
+        if let Some(fragment_output) = config.fragment_output.as_ref() {
+            self.write_fragment(fragment_output, most_recent(&book_posts, config.target_number_of_entries), config)?;
+        }
+        Ok(())
+    }
 
-                last_endpoint = full_match.end();
-                processed_content.push_str(self.generate_markdown(posts, "").as_str());
+    // Themes can't use directives, so they get the recently updated list as html to include instead.
+    fn write_fragment(&self, path: &Path, posts: &[&Post], config: &UpdatedConfig) -> io::Result<()> {
+        // Every entry is made a list item, so the fragment is always a single <ul>.
+        let markdown = posts.iter()
+            .filter_map(|post| {
+                let link = post.list_link(&config.template, &config.entry_authors, &config.author_limit, &config.timezone)?;
+                let item = link.strip_prefix("- ").or_else(|| link.strip_prefix("* ")).unwrap_or(&link);
+                Some(format!("- {}\n", item))
+            })
+            .collect::<String>();
+        let fragment = if markdown.is_empty() { "<ul>\n</ul>\n".to_string() } else { render_html(&markdown) };
 
-            // processed_content.push_str(self.generate_markdown(posts, indentation.as_str()).as_str());
-            // }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
         }
+        write_if_changed(path, &fragment).map(|_| ())
+    }
 
-        if content.len() > last_endpoint {
-            processed_content.push_str(&content[last_endpoint..content.len()]);
-        }
+    // listed_posts are the posts recently updated lists in this chapter can list, and post_map every post in the book by
+    // its path. chapter_path is the path of the chapter being processed, which is None for draft chapters.
+    fn process_chapter(&self, content: &str, listed_posts: &[&Post], chapter_path: Option<&Path>, post_map: &HashMap<PathBuf, &Post>, (statistics, author_statistics): (&BookStatistics, &HashMap<Author, AuthorStats>), config: &UpdatedConfig) -> String {
+        // Chapters that aren't committed yet have no post, but their directives still get replaced.
+        let chapter_post = chapter_path.and_then(|path| post_map.get(path).copied());
+        let last_modified = chapter_post
+            .map(|post| formatted_date(&post.last_modified_date, &config.timezone))
+            .unwrap_or_else(|| config.missing_date_text.to_string());
+        let created_date = chapter_post
+            .map(|post| formatted_date(&post.created_date, &config.timezone))
+            .unwrap_or_else(|| config.missing_date_text.to_string());
 
-        processed_content
+        replace_directives(content, |capture| {
+            if capture.name("recently_updated").is_some() {
+                // count=N overrides target_number_of_entries for this list only.
+                let posts = match capture.name("count").and_then(|count| count.as_str().parse::<i64>().ok()) {
+                    Some(count) => most_recent(listed_posts, count),
+                    None => most_recent(listed_posts, config.target_number_of_entries),
+                };
+                let heading = capture.name("title").map(|title| (title.as_str(), config.heading_level));
+                self.generate_content(posts, config, "", &path_to_root(chapter_path), heading)
+            } else if capture.name("contributors").is_some() {
+                let template = capture.name("template").map_or(DEFAULT_CONTRIBUTORS_TEMPLATE, |template| template.as_str());
+                self.generate_contributors(post_map.values().copied(), template)
+            } else if let Some(field) = capture.name("field") {
+                book_statistic(statistics, field.as_str(), config).unwrap_or_else(|| {
+                    log::warn!("Unknown book_stats field \"{}\"", field.as_str());
+                    capture[0].to_string()
+                })
+            } else if let (Some(author), Some(field)) = (capture.name("author"), capture.name("author_field")) {
+                author_statistic(author_statistics, author.as_str(), field.as_str(), config).unwrap_or_else(|| {
+                    log::warn!("Unknown author_stats field \"{}\"", field.as_str());
+                    capture[0].to_string()
+                })
+            } else if capture.name("feed_url").is_some() {
+                chapter_feed_url(config, chapter_path).unwrap_or_else(|| {
+                    log::warn!("No feed url to replace {{{{#feed_url}}}} with. Run the git-atom preprocessor before git-updated.");
+                    capture[0].to_string()
+                })
+            } else if capture.name("last_modified").is_some() {
+                last_modified.to_string()
+            } else {
+                created_date.to_string()
+            }
+        })
     }
 
-    fn generate_markdown(&self, posts: &Vec<Post>, indentation_prefix: &str) -> String {
-        posts.iter()
-            .map({ |post|
-                format!("{}{}", indentation_prefix, post.list_link())
+    // Renderers that directives aren't expanded for shouldn't show them either, so they're removed.
+    pub(crate) fn strip_directives(&self, book: &mut Book) {
+        book.for_each_mut(|item| {
+            if let BookItem::Chapter(chapter) = item {
+                chapter.content = replace_directives(&chapter.content, |_| String::new());
+            }
+        });
+    }
+
+    // Lists everyone who authored any chapter, by the number of chapters they contributed to and then by name.
+    fn generate_contributors<'a>(&self, posts: impl Iterator<Item = &'a Post>, template: &str) -> String {
+        let mut pages: HashMap<&Author, usize> = HashMap::new();
+        for author in posts.flat_map(|post| post.authors.keys()) {
+            *pages.entry(author).or_insert(0) += 1;
+        }
+        let mut contributors: Vec<(&Author, usize)> = pages.into_iter().collect();
+        contributors.sort_by(|(a, a_pages), (b, b_pages)| b_pages.cmp(a_pages).then_with(|| a.cmp(b)));
+
+        contributors.iter()
+            .map(|(author, pages)| {
+                template
+                    .replace("{name}", &author.name)
+                    .replace("{pages}", &pages.to_string())
             })
             .fold(String::new(), |a, b| a + &b + "\n")
     }
+
+    // Links in asides are html, which mdbook doesn't make relative to the book's root, so they start with
+    // path_to_root instead.
+    // heading is the title of the list and the level of its heading, from the directive's title="..." and the
+    // heading_level option.
+    fn generate_content(&self, posts: &[&Post], config: &UpdatedConfig, indentation_prefix: &str, path_to_root: &str, heading: Option<(&str, u32)>) -> String {
+        match config.recently_updated_style {
+            RecentlyUpdatedStyle::List => {
+                let heading = heading.map_or(String::new(), |(title, level)| format!("{} {}\n\n", "#".repeat(level as usize), title));
+                posts.iter()
+                    .filter_map({ |post|
+                        Some(format!("{}{}", indentation_prefix, post.list_link(&config.template, &config.entry_authors, &config.author_limit, &config.timezone)?))
+                    })
+                    .fold(heading, |a, b| a + &b + "\n")
+            }
+            RecentlyUpdatedStyle::Aside => {
+                let heading = heading.map_or(String::new(), |(title, level)| format!("<h{}>{}</h{}>\n", level, html_escape::encode_text(title), level));
+                let items = posts.iter()
+                    .filter_map(|post| post.aside_item(path_to_root, &config.timezone))
+                    .collect::<String>();
+                format!("<aside class=\"recently-updated\">\n{}<ul>\n{}</ul>\n</aside>\n", heading, items)
+            }
+        }
+    }
 }
 
 impl Post {
-    fn list_link(&self) -> String {
-        let last_modified_naivedatetime = chrono::NaiveDateTime::from_timestamp(self.last_modified_date.seconds(), 0);
+    fn aside_item(&self, path_to_root: &str, timezone: &DisplayTimezone) -> Option<String> {
+        let url = format!("{}{}", path_to_root, self.source_url(None)?);
+        Some(format!(
+            "<li><a href=\"{}\">{}</a> <time datetime=\"{}\">{}</time></li>\n",
+            html_escape::encode_double_quoted_attribute(&url),
+            html_escape::encode_text(&self.title),
+            rfc3339_date(&self.last_modified_date),
+            formatted_date(&self.last_modified_date, timezone)
+        ))
+    }
+
+    fn list_link(&self, template: &str, entry_authors: &EntryAuthors, author_limit: &AuthorLimit, timezone: &DisplayTimezone) -> Option<String> {
+        let (authors, overflow_author) = self.limited_authors(entry_authors, author_limit);
+        let authors = authors
+            .iter()
+            .map(|author| author.name.as_str())
+            .chain(overflow_author.as_deref())
+            .collect::<Vec<&str>>()
+            .join(", ");
+        let url = self.source_url(None)?;
+        Some(template
+            .replace("{title}", &escape_markdown(&self.title))
+            .replace("{url}", &url)
+            .replace("{last_modified}", &formatted_date(&self.last_modified_date, timezone))
+            .replace("{word_count}", &self.word_count.unwrap_or(0).to_string())
+            .replace("{reading_time}", &self.reading_time.unwrap_or(0).to_string())
+            .replace("{authors}", &authors)
+            .replace("{section}", self.section.as_deref().unwrap_or(""))
+            .replace("{changed_sections}", &self.changed_sections.join(", "))
+            .replace("{churn}", &self.churn.to_string())
+            .replace("{revisions}", &self.revision_count.to_string()))
+    }
+}
+
+// Titles are plain text, so characters that markdown would treat as links, emphasis, code or html are escaped.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        if matches!(character, '\\' | '[' | ']' | '`' | '*' | '_' | '<' | '&') {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+// Replaces every directive in content with what replacement returns for it. Directives in html comments or code are
+// left alone, and escaped ones, like \{{#recently_updated}}, are written out without the backslash the same as mdbook's
+// own {{#include}}.
+fn replace_directives(content: &str, mut replacement: impl FnMut(&Captures) -> String) -> String {
+    let directive_regex = Regex::new(concat!(
+        r"(?P<escape>\\)?\{\{#(?:",
+        r#"(?P<recently_updated>recently_updated(?:\s+(?:count=(?P<count>\d+)|title="(?P<title>[^"]*)"))*\s*)"#,
+        r#"|(?P<contributors>contributors(?:\s+template="(?P<template>[^"]*)")?\s*)"#,
+        r#"|book_stats\s+field="(?P<field>[^"]*)"\s*"#,
+        r#"|author_stats\s+author="(?P<author>[^"]*)"\s+field="(?P<author_field>[^"]*)"\s*"#,
+        r"|(?P<feed_url>feed_url)",
+        r"|(?P<last_modified>last_modified)",
+        r"|created_date",
+        r")}}",
+    )).unwrap();
+
+    let ignored = ignored_ranges(content);
+    let mut processed_content = String::new();
+    let mut last_endpoint: usize = 0;
+
+    for capture in directive_regex.captures_iter(content) {
+        let full_match = capture.get(0).unwrap();
+        if ignored.iter().any(|range| range.contains(&full_match.start())) {
+            continue;
+        }
+        processed_content.push_str(&content[last_endpoint..full_match.start()]);
+        last_endpoint = full_match.end();
+
+        if capture.name("escape").is_some() {
+            processed_content.push_str(&full_match.as_str()[1..]);
+        } else {
+            processed_content.push_str(&replacement(&capture));
+        }
+    }
+
+    if content.len() > last_endpoint {
+        processed_content.push_str(&content[last_endpoint..content.len()]);
+    }
+
+    processed_content
+}
+
+// Byte ranges of the html comments and code in content, where directives are left alone. Commenting a directive
+// out switches it off, and chapters can show directives in code without them being replaced.
+fn ignored_ranges(content: &str) -> Vec<Range<usize>> {
+    let comment_regex = Regex::new(r"(?s)<!--.*?-->").unwrap();
+    let mut ranges: Vec<Range<usize>> = comment_regex.find_iter(content).map(|comment| comment.range()).collect();
+    ranges.extend(Parser::new(content).into_offset_iter().filter_map(|(event, range)| match event {
+        Event::Start(Tag::CodeBlock(_)) | Event::Code(_) => Some(range),
+        _ => None,
+    }));
+    ranges
+}
+
+fn book_statistic(statistics: &BookStatistics, field: &str, config: &UpdatedConfig) -> Option<String> {
+    let date = |time: Option<Time>| time
+        .map(|time| formatted_date(&time, &config.timezone))
+        .unwrap_or_else(|| config.missing_date_text.to_string());
+    match field {
+        "total_chapters" => Some(statistics.total_chapters.to_string()),
+        "total_authors" => Some(statistics.total_authors.to_string()),
+        "total_commits" => Some(statistics.total_commits.to_string()),
+        "first_commit" => Some(date(statistics.first_commit)),
+        "latest_commit" => Some(date(statistics.latest_commit)),
+        _ => None,
+    }
+}
 
-        let last_modified_datetime = chrono::DateTime::<FixedOffset>::from_utc(last_modified_naivedatetime, chrono::FixedOffset::east(0));
-        format!("- [{}](/{}) ({})", self.title, self.source_url(None).expect("Actual link"), last_modified_datetime.format("%Y-%m-%d"))
+// author is matched by name or email. Authors with several emails are counted by the one that authored the most chapters.
+fn author_statistic(author_statistics: &HashMap<Author, AuthorStats>, author: &str, field: &str, config: &UpdatedConfig) -> Option<String> {
+    let author_stats = author_statistics.iter()
+        .filter(|(candidate, _)| candidate.name == author || candidate.email.as_deref() == Some(author))
+        .map(|(_, author_stats)| author_stats)
+        .max_by_key(|author_stats| author_stats.chapters_authored);
+    let date = |time: Option<Time>| time
+        .map(|time| formatted_date(&time, &config.timezone))
+        .unwrap_or_else(|| config.missing_date_text.to_string());
+    match field {
+        "chapters_authored" => Some(author_stats.map_or(0, |author_stats| author_stats.chapters_authored).to_string()),
+        "chapters_last_modified" => Some(author_stats.map_or(0, |author_stats| author_stats.chapters_last_modified).to_string()),
+        "first_contribution" => Some(date(author_stats.map(|author_stats| author_stats.first_contribution))),
+        "latest_contribution" => Some(date(author_stats.map(|author_stats| author_stats.latest_contribution))),
+        _ => None,
     }
-}
\ No newline at end of file
+}
+
+// The url of the feed chapter_path is in, which is its translation's own feed in translated books.
+fn chapter_feed_url(config: &UpdatedConfig, chapter_path: Option<&Path>) -> Option<String> {
+    let feed_url = config.feed_url.as_ref()?;
+    let translation = chapter_path.and_then(|path| config.translations.iter().find(|language| path.starts_with(language)));
+    match translation {
+        Some(translation) => Some(Url::parse(feed_url).ok()?.join(&format!("{}/atom.xml", translation)).ok()?.to_string()),
+        None => Some(feed_url.to_string()),
+    }
+}
+
+// "../" for every directory the chapter at path, relative to the src directory, is in.
+fn path_to_root(path: Option<&Path>) -> String {
+    let depth = path.map_or(0, |path| path.components().count().saturating_sub(1));
+    "../".repeat(depth)
+}
+
+fn rfc3339_date(time: &Time) -> String {
+    let offset = FixedOffset::east_opt(time.offset_minutes() * 60).unwrap_or_else(|| FixedOffset::east(0));
+    chrono::DateTime::<FixedOffset>::from_utc(chrono::NaiveDateTime::from_timestamp(time.seconds(), 0), offset).to_rfc3339()
+}
+
+fn formatted_date(time: &Time, timezone: &DisplayTimezone) -> String {
+    let naive_date_time = chrono::NaiveDateTime::from_timestamp(time.seconds(), 0);
+
+    let date_time = chrono::DateTime::<chrono::Utc>::from_utc(naive_date_time, chrono::Utc);
+    match timezone {
+        DisplayTimezone::CommitOffset => {
+            let offset = FixedOffset::east_opt(time.offset_minutes() * 60).unwrap_or_else(|| FixedOffset::east(0));
+            date_time.with_timezone(&offset).format("%Y-%m-%d").to_string()
+        }
+        DisplayTimezone::Fixed(offset) => date_time.with_timezone(offset).format("%Y-%m-%d").to_string(),
+        #[cfg(feature = "timezones")]
+        DisplayTimezone::Named(timezone) => date_time.with_timezone(timezone).format("%Y-%m-%d").to_string(),
+    }
+}
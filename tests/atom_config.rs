@@ -0,0 +1,47 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file};
+
+#[test]
+fn appends_a_slash_to_a_base_url_without_one() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 0);
+
+    let xml = atom_feed(root, json!({ "base_url": "https://example.com/book" }), vec![chapter("Guide", "guide.md", "# Guide\n")]);
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+
+    // Without the slash, joining the chapter's path would replace "book".
+    assert_eq!(feed.entries[0].links[0].href, "https://example.com/book/guide.html");
+}
+
+fn preview(options: serde_json::Value) -> String {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    let markdown = "# Guide\n\nFirst\n\nSecond\n";
+    commit_file(&repo, root, "src/guide.md", markdown, 0);
+
+    let xml = atom_feed(root, options, vec![chapter("Guide", "guide.md", markdown)]);
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+    let content = feed.entries[0].content.as_ref().unwrap();
+    content.value.clone().unwrap_or_default()
+}
+
+#[test]
+fn prefers_preview_lines_to_article_preview_lines() {
+    assert_eq!(preview(json!({ "base_url": "https://example.com/book/", "preview_lines": 1, "article_preview_lines": -1 })), "<p>First</p>\n");
+    assert_eq!(preview(json!({ "base_url": "https://example.com/book/", "preview_lines": -1, "article_preview_lines": 1 })), "<p>First</p>\n<p>Second</p>\n");
+}
+
+#[test]
+fn still_reads_article_preview_lines() {
+    assert_eq!(preview(json!({ "base_url": "https://example.com/book/", "article_preview_lines": 1 })), "<p>First</p>\n");
+    assert_eq!(preview(json!({ "base_url": "https://example.com/book/", "article_preview_lines": -1 })), "<p>First</p>\n<p>Second</p>\n");
+}
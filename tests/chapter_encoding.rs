@@ -0,0 +1,76 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use git2::Repository;
+use mdbook::book::Book;
+use mdbook_git_atom::errors::{PostError, SearchError};
+use mdbook_git_atom::post_finder::{InvalidUtf8, PostFinder, SortDirection, SortField};
+use common::{chapter, commit_bytes};
+
+const INVALID_UTF8: &[u8] = b"# Caf\xe9\n\nCaf\xe9 au lait\n";
+
+fn repository_with(content: &[u8]) -> tempfile::TempDir {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_bytes(&repo, root, "src/chapter.md", content, 0);
+    directory
+}
+
+fn post_content(root: &Path, invalid_utf8: InvalidUtf8) -> Result<Option<String>, PostError> {
+    PostFinder::new(root)
+        .with_invalid_utf8(invalid_utf8)
+        .post_for_chapter(root.join("src/chapter.md"), "Café".to_string(), "chapter.md".into(), Some(-1))
+        .map(|post| post.content().map(|content| content.to_string()))
+}
+
+#[test]
+fn leaves_out_a_byte_order_mark() {
+    let directory = repository_with("\u{feff}# Café\n\nCafé au lait\n".as_bytes());
+
+    let content = post_content(directory.path(), InvalidUtf8::Lossy).unwrap();
+    assert_eq!(content.as_deref(), Some("<p>Café au lait</p>\n"));
+}
+
+#[test]
+fn replaces_invalid_bytes_when_lossy() {
+    let directory = repository_with(INVALID_UTF8);
+
+    let content = post_content(directory.path(), InvalidUtf8::Lossy).unwrap();
+    assert_eq!(content.as_deref(), Some("<h1>Caf\u{fffd}</h1>\n<p>Caf\u{fffd} au lait</p>\n"));
+}
+
+#[test]
+fn leaves_out_the_content_when_skipping() {
+    let directory = repository_with(INVALID_UTF8);
+
+    // The chapter is still a post, dated by its history.
+    let content = post_content(directory.path(), InvalidUtf8::Skip).unwrap();
+    assert_eq!(content, None);
+}
+
+#[test]
+fn fails_with_the_path_when_erroring() {
+    let directory = repository_with(INVALID_UTF8);
+    let root = directory.path();
+
+    match post_content(root, InvalidUtf8::Error) {
+        Err(PostError::InvalidUtf8(path)) => assert_eq!(path, root.join("src/chapter.md")),
+        result => panic!("Expected an invalid UTF-8 error, got {:?}", result),
+    }
+
+    // Searches fail too, so mdbook reports the error instead of publishing the rest of the book.
+    let book: Book = serde_json::from_value(serde_json::json!({
+        "sections": [chapter("Café", "chapter.md", "")],
+        "__non_exhaustive": null,
+    })).unwrap();
+    let result = PostFinder::new(root)
+        .with_invalid_utf8(InvalidUtf8::Error)
+        .search(&book, &root.join("src"), Some(-1), 0, SortField::LastModified, SortDirection::Descending);
+    match result {
+        Err(error @ SearchError::Chapter(PostError::InvalidUtf8(_))) => assert!(error.to_string().contains("chapter.md isn't valid UTF-8"), "{}", error),
+        result => panic!("Expected an invalid UTF-8 error, got {:?}", result),
+    }
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file};
+
+// The commit range comes from the environment, which every test in this file would share, so there's only one.
+#[test]
+fn keeps_chapters_outside_the_commit_range_alive() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 0);
+    commit_file(&repo, root, "src/old.md", "# Old\n", 1);
+    let from = repo.head().unwrap().target().unwrap();
+    let sections = || vec![chapter("Guide", "guide.md", "# Guide\n\nMore.\n"), chapter("Old", "old.md", "# Old\n")];
+    let options = json!({ "base_url": "https://example.com/book/", "tombstones": true });
+    atom_feed(root, options.clone(), sections());
+
+    // Old wasn't changed in the range, so it's left out of the feed.
+    commit_file(&repo, root, "src/guide.md", "# Guide\n\nMore.\n", 10);
+    let to = repo.head().unwrap().target().unwrap();
+    std::env::set_var("MDBOOK_GIT_ATOM_FROM_COMMIT", from.to_string());
+    std::env::set_var("MDBOOK_GIT_ATOM_TO_COMMIT", to.to_string());
+    let xml = atom_feed(root, options, sections());
+    std::env::remove_var("MDBOOK_GIT_ATOM_FROM_COMMIT");
+    std::env::remove_var("MDBOOK_GIT_ATOM_TO_COMMIT");
+
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+    assert_eq!(feed.entries.len(), 1);
+    assert!(!feed.extensions.contains_key("at"), "{}", xml);
+}
@@ -0,0 +1,81 @@
+// Each test only uses some of these helpers.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use git2::{Repository, Signature, Time};
+use mdbook::preprocess::{CmdPreprocessor, Preprocessor};
+use mdbook_git_atom::atom_processor::AtomProcessor;
+
+// Chapters are read, and feeds written, relative to the current directory, which tests share, so only one runs a
+// processor at a time.
+pub static CURRENT_DIRECTORY: Mutex<()> = Mutex::new(());
+
+// Commits a file, the given number of minutes after the first commit, so chapters are ordered by when they were written.
+pub fn commit_file(repo: &Repository, root: &Path, path: &str, content: &str, minutes: i64) {
+    commit_bytes(repo, root, path, content.as_bytes(), minutes);
+}
+
+// The same as commit_file, for content that isn't text.
+pub fn commit_bytes(repo: &Repository, root: &Path, path: &str, content: &[u8], minutes: i64) {
+    commit_at_offset(repo, root, path, content, minutes, 0);
+}
+
+// The same as commit_bytes, made by someone offset_minutes ahead of UTC.
+pub fn commit_at_offset(repo: &Repository, root: &Path, path: &str, content: &[u8], minutes: i64, offset_minutes: i32) {
+    fs::write(root.join(path), content).unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(path)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+    let signature = Signature::new("Alice", "alice@example.com", &Time::new(1_600_000_000 + minutes * 60, offset_minutes)).unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &signature, &signature, path, &tree, &parents).unwrap();
+}
+
+pub fn chapter(name: &str, path: &str, content: &str) -> serde_json::Value {
+    serde_json::json!({
+        "Chapter": {
+            "name": name,
+            "content": content,
+            "number": null,
+            "sub_items": [],
+            "path": path,
+            "source_path": path,
+            "parent_names": [],
+        }
+    })
+}
+
+// Runs the atom preprocessor over the book at root, with the given options in its section, and returns the feed it
+// writes.
+pub fn atom_feed(root: &Path, options: serde_json::Value, sections: Vec<serde_json::Value>) -> String {
+    run_atom_processor(root, options, sections);
+    fs::read_to_string(root.join("src/atom.xml")).unwrap()
+}
+
+// Runs the atom preprocessor over the book at root, with the given options in its section, for tests of books with
+// more than one feed.
+pub fn run_atom_processor(root: &Path, options: serde_json::Value, sections: Vec<serde_json::Value>) {
+    let input = serde_json::json!([
+        {
+            "root": root,
+            "config": {
+                "book": { "title": "Test Book", "src": "src", "language": "en" },
+                "preprocessor": { "git-atom": options },
+            },
+            "renderer": "html",
+            "mdbook_version": "0.4.21",
+        },
+        { "sections": sections, "__non_exhaustive": null },
+    ]);
+    let (ctx, book) = CmdPreprocessor::parse_input(input.to_string().as_bytes()).unwrap();
+
+    let _guard = CURRENT_DIRECTORY.lock().unwrap_or_else(|error| error.into_inner());
+    std::env::set_current_dir(root).unwrap();
+    AtomProcessor::new().run(&ctx, book).unwrap();
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use git2::{Repository, Time};
+use mdbook::book::Book;
+use mdbook_git_atom::post_finder::{DateOverride, PostFinder};
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file};
+
+const FRONT_MATTER: &str = "+++\npublished = 2010-01-01T00:00:00Z\n+++\n\n# Imported\n";
+
+// The published and updated dates of each entry, by title.
+fn entry_dates(xml: &str) -> HashMap<String, (String, String)> {
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+    feed.entries.iter()
+        .map(|entry| (entry.title.value.to_string(), (entry.published.unwrap().to_rfc3339(), entry.updated.to_rfc3339())))
+        .collect()
+}
+
+#[test]
+fn prefers_front_matter_then_the_dates_file_then_git() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/imported.md", FRONT_MATTER, 0);
+    commit_file(&repo, root, "src/other.md", "# Other\n", 10);
+    commit_file(&repo, root, "src/new.md", "# New\n", 20);
+    fs::write(root.join("dates.toml"), concat!(
+        "\"imported.md\" = { published = 2015-03-01, updated = 2016-07-12T10:00:00Z }\n",
+        "\"other.md\" = { published = \"2014-11-20T08:30:00+01:00\" }\n",
+    )).unwrap();
+
+    let sections = vec![chapter("Imported", "imported.md", FRONT_MATTER), chapter("Other", "other.md", "# Other\n"), chapter("New", "new.md", "# New\n")];
+    let dates = entry_dates(&atom_feed(root, json!({ "base_url": "https://example.com/book/" }), sections));
+
+    // The front matter's published date wins over the file's, which still gives the updated date.
+    assert_eq!(dates["Imported"], ("2010-01-01T00:00:00+00:00".to_string(), "2016-07-12T10:00:00+00:00".to_string()));
+    // Dates missing from the file come from git.
+    assert_eq!(dates["Other"], ("2014-11-20T07:30:00+00:00".to_string(), "2020-09-13T12:36:40+00:00".to_string()));
+    assert_eq!(dates["New"], ("2020-09-13T12:46:40+00:00".to_string(), "2020-09-13T12:46:40+00:00".to_string()));
+}
+
+#[test]
+fn uses_dates_from_git_without_a_dates_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/new.md", "# New\n", 0);
+
+    let sections = vec![chapter("New", "new.md", "# New\n")];
+    let dates = entry_dates(&atom_feed(root, json!({ "base_url": "https://example.com/book/", "dates_file": "missing.toml" }), sections));
+
+    assert_eq!(dates["New"], ("2020-09-13T12:26:40+00:00".to_string(), "2020-09-13T12:26:40+00:00".to_string()));
+}
+
+#[test]
+fn finds_dates_for_paths_that_are_not_chapters() {
+    let book: Book = serde_json::from_value(json!({
+        "sections": [chapter("Imported", "wiki/imported.md", "# Imported\n")],
+        "__non_exhaustive": null,
+    })).unwrap();
+    let date_override = DateOverride { published: Some(Time::new(1_425_168_000, 0)), updated: None };
+    let date_overrides = HashMap::from([
+        (PathBuf::from("wiki/imported.md"), date_override),
+        (PathBuf::from("wiki/imported-typo.md"), date_override),
+    ]);
+
+    let post_finder = PostFinder::new(".").with_date_overrides(date_overrides);
+
+    assert_eq!(post_finder.unknown_date_overrides(&book), vec![PathBuf::from("wiki/imported-typo.md")]);
+}
@@ -0,0 +1,32 @@
+use git2::Time;
+use mdbook_git_atom::atom_processor::{AtomGenerator, EntryOrder};
+use mdbook_git_atom::post_finder::Post;
+use url::Url;
+
+// Posts in neither order, as if they were picked by title. Created dates run the opposite way to updated dates, so
+// only sorting by the updated date gives the expected order.
+fn posts() -> Vec<Post> {
+    vec![
+        Post::new("b.md", "Updated second").with_dates(Time::new(1_600_000_200, 0), Time::new(1_600_002_000, 0)),
+        Post::new("c.md", "Updated third").with_dates(Time::new(1_600_000_100, 0), Time::new(1_600_003_000, 0)),
+        Post::new("a.md", "Updated first").with_dates(Time::new(1_600_000_300, 0), Time::new(1_600_001_000, 0)),
+    ]
+}
+
+fn entry_titles(generator: AtomGenerator) -> Vec<String> {
+    let feed = generator.generate(posts(), "Test Book".to_string(), Url::parse("https://example.com/book/").unwrap()).unwrap();
+    let xml = feed.to_string();
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+    feed.entries.iter().map(|entry| entry.title.value.clone()).collect()
+}
+
+#[test]
+fn lists_the_most_recently_updated_entries_first_by_default() {
+    assert_eq!(entry_titles(AtomGenerator::new()), ["Updated third", "Updated second", "Updated first"]);
+}
+
+#[test]
+fn lists_the_least_recently_updated_entries_first_when_oldest_first() {
+    let generator = AtomGenerator::new().with_entry_order(EntryOrder::OldestFirst);
+    assert_eq!(entry_titles(generator), ["Updated first", "Updated second", "Updated third"]);
+}
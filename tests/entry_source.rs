@@ -0,0 +1,72 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file, run_atom_processor};
+
+#[test]
+fn entry_sources_describe_the_feed_and_round_trip() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/README.md", "# Introduction\n", 0);
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 10);
+
+    let sections = vec![chapter("Introduction", "README.md", "# Introduction\n"), chapter("Guide", "guide.md", "# Guide\n")];
+    let xml = atom_feed(root, json!({ "base_url": "https://example.com/book/", "include_source": true }), sections);
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+
+    assert_eq!(feed.id, "https://example.com/book/");
+    assert_eq!(feed.entries.len(), 2);
+    for entry in feed.entries.iter() {
+        let source = entry.source.as_ref().expect("Every entry should have a source");
+        assert_eq!(source.id, feed.id);
+        assert_eq!(source.title.value, "Test Book");
+        assert_eq!(source.updated, feed.updated);
+        assert_eq!(source.contributors.iter().map(|person| person.name.as_str()).collect::<Vec<&str>>(), ["Alice"]);
+    }
+
+    // Writing the parsed feed again keeps the sources.
+    let reparsed = feed.to_string().parse::<atom_syndication::Feed>().unwrap();
+    assert_eq!(reparsed.entries[0].source, feed.entries[0].source);
+}
+
+#[test]
+fn leaves_out_entry_sources_by_default() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/README.md", "# Introduction\n", 0);
+
+    let xml = atom_feed(root, json!({ "base_url": "https://example.com/book/" }), vec![chapter("Introduction", "README.md", "# Introduction\n")]);
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+
+    assert!(feed.entries[0].source.is_none());
+}
+
+#[test]
+fn entry_sources_name_the_translations_feed_they_came_from() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src/en")).unwrap();
+    fs::create_dir_all(root.join("src/de")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/en/guide.md", "# Guide\n", 0);
+    commit_file(&repo, root, "src/de/guide.md", "# Anleitung\n", 10);
+
+    let sections = vec![chapter("Guide", "en/guide.md", "# Guide\n"), chapter("Anleitung", "de/guide.md", "# Anleitung\n")];
+    let options = json!({ "base_url": "https://example.com/book/", "include_source": true, "translations": ["en", "de"] });
+    run_atom_processor(root, options, sections);
+
+    for language in ["en", "de"] {
+        let xml = fs::read_to_string(root.join("src").join(language).join("atom.xml")).unwrap();
+        let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+        let reparsed = feed.to_string().parse::<atom_syndication::Feed>().unwrap();
+        let source = reparsed.entries[0].source.as_ref().expect("Every entry should have a source");
+        assert_eq!(source.id, format!("https://example.com/book/{}/atom.xml", language));
+        assert_eq!(source.id, feed.id);
+    }
+}
@@ -0,0 +1,24 @@
+use std::io::BufReader;
+use git2::Time;
+use mdbook_git_atom::atom_processor::AtomGenerator;
+use mdbook_git_atom::post_finder::Post;
+use url::Url;
+
+const CONTENT: &str = "<p>Hello &amp; <em>world</em>, &lt;tags&gt; and \"quotes\".</p>\n<pre><code>if a &lt; b {}\n</code></pre>\n";
+
+#[test]
+fn entry_content_round_trips_through_the_feed() {
+    let post = Post::new("README.md", "Introduction")
+        .with_dates(Time::new(1_600_000_000, 0), Time::new(1_600_000_000, 0))
+        .with_content(CONTENT);
+    let feed = AtomGenerator::new().generate(vec![post], "Test Book".to_string(), Url::parse("https://example.com/book/").unwrap()).unwrap();
+    let xml = feed.to_string();
+
+    // The html is escaped once, as the text of an html content element.
+    assert!(xml.contains("&lt;p&gt;Hello &amp;amp; &lt;em&gt;world&lt;/em&gt;"), "{}", xml);
+
+    let feed = atom_syndication::Feed::read_from(BufReader::new(xml.as_bytes())).unwrap();
+    let content = feed.entries[0].content.as_ref().unwrap();
+    assert_eq!(content.content_type.as_deref(), Some("html"));
+    assert_eq!(content.value.as_deref(), Some(CONTENT));
+}
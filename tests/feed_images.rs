@@ -0,0 +1,73 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use mdbook::Config;
+use mdbook_git_atom::atom_processor::AtomConfig;
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file};
+
+fn book_root() -> tempfile::TempDir {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src/images")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 0);
+    directory
+}
+
+#[test]
+fn links_to_images_given_as_urls() {
+    let directory = book_root();
+    let options = json!({
+        "base_url": "https://example.com/book",
+        "icon": "https://cdn.example.com/icon.png",
+        "logo": "https://cdn.example.com/logo.png",
+    });
+    let xml = atom_feed(directory.path(), options, vec![chapter("Guide", "guide.md", "# Guide\n")]);
+
+    assert!(xml.contains("<icon>https://cdn.example.com/icon.png</icon>"), "{}", xml);
+    assert!(xml.contains("<logo>https://cdn.example.com/logo.png</logo>"), "{}", xml);
+}
+
+#[test]
+fn links_to_images_in_the_src_directory_under_base_url() {
+    let directory = book_root();
+    fs::write(directory.path().join("src/images/icon.png"), b"icon").unwrap();
+    fs::write(directory.path().join("src/logo.png"), b"logo").unwrap();
+    let options = json!({
+        "base_url": "https://example.com/book",
+        "icon": "images/icon.png",
+        "logo": "./logo.png",
+    });
+    let xml = atom_feed(directory.path(), options, vec![chapter("Guide", "guide.md", "# Guide\n")]);
+
+    assert!(xml.contains("<icon>https://example.com/book/images/icon.png</icon>"), "{}", xml);
+    assert!(xml.contains("<logo>https://example.com/book/logo.png</logo>"), "{}", xml);
+}
+
+#[test]
+fn fails_on_a_missing_image_by_default() {
+    let directory = book_root();
+    let section: toml::value::Table = toml::from_str(r#"
+        base_url = "https://example.com/book"
+        icon = "images/missing.png"
+    "#).unwrap();
+
+    let error = AtomConfig::from_section(directory.path(), &Config::default(), &section).err().expect("A missing icon is an error");
+
+    assert!(error.to_string().contains("images/missing.png doesn't exist"), "{}", error);
+}
+
+#[test]
+fn still_links_to_a_missing_image_when_only_warning() {
+    let directory = book_root();
+    let options = json!({
+        "base_url": "https://example.com/book",
+        "logo": "images/missing.png",
+        "on_missing_asset": "warn",
+    });
+    let xml = atom_feed(directory.path(), options, vec![chapter("Guide", "guide.md", "# Guide\n")]);
+
+    assert!(xml.contains("<logo>https://example.com/book/images/missing.png</logo>"), "{}", xml);
+}
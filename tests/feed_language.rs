@@ -0,0 +1,49 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file};
+
+// The attributes of the feed element, which atom_syndication doesn't read.
+fn feed_element(xml: &str) -> &str {
+    let start = xml.find("<feed ").unwrap();
+    &xml[start..start + xml[start..].find('>').unwrap()]
+}
+
+#[test]
+fn sets_the_language_on_the_feed_and_its_entries() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Anleitung\n", 0);
+
+    // The preprocessor's language overrides the book's, which is "en".
+    let xml = atom_feed(root, json!({ "base_url": "https://example.com/book/", "language": "de" }), vec![chapter("Anleitung", "guide.md", "# Anleitung\n")]);
+
+    assert!(feed_element(&xml).contains(" xml:lang=\"de\""), "{}", xml);
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+    assert_eq!(feed.title.lang.as_deref(), Some("de"));
+    let entry = &feed.entries[0];
+    assert_eq!(entry.title.lang.as_deref(), Some("de"));
+    assert_eq!(entry.content.as_ref().unwrap().lang.as_deref(), Some("de"));
+    assert_eq!(entry.links[0].hreflang.as_deref(), Some("de"));
+}
+
+#[test]
+fn uses_the_books_language_by_default() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 0);
+
+    let xml = atom_feed(root, json!({ "base_url": "https://example.com/book/" }), vec![chapter("Guide", "guide.md", "# Guide\n")]);
+
+    assert!(feed_element(&xml).contains(" xml:lang=\"en\""), "{}", xml);
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+    assert_eq!(feed.title.lang.as_deref(), Some("en"));
+    assert_eq!(feed.entries[0].content.as_ref().unwrap().lang.as_deref(), Some("en"));
+    assert_eq!(feed.entries[0].links[0].hreflang.as_deref(), Some("en"));
+}
@@ -0,0 +1,20 @@
+use std::io::BufReader;
+use git2::Time;
+use mdbook_git_atom::atom_processor::AtomGenerator;
+use mdbook_git_atom::post_finder::Post;
+use url::Url;
+
+#[test]
+fn names_the_version_that_generated_the_feed() {
+    let post = Post::new("README.md", "Introduction").with_dates(Time::new(1_600_000_000, 0), Time::new(1_600_000_000, 0));
+    let feed = AtomGenerator::new().generate(vec![post], "Test Book".to_string(), Url::parse("https://example.com/book/").unwrap()).unwrap();
+    let xml = feed.to_string();
+
+    assert!(xml.contains("xmlns:mdbook-git-atom=\"https://github.com/younata/mdbook-git-atom\""), "{}", xml);
+    assert!(xml.contains(&format!("<mdbook-git-atom:version>{}</mdbook-git-atom:version>", env!("CARGO_PKG_VERSION"))), "{}", xml);
+
+    let feed = atom_syndication::Feed::read_from(BufReader::new(xml.as_bytes())).unwrap();
+    assert_eq!(feed.namespaces.get("mdbook-git-atom").map(|namespace| namespace.as_str()), Some("https://github.com/younata/mdbook-git-atom"));
+    let version = &feed.extensions["mdbook-git-atom"]["version"][0];
+    assert_eq!(version.value.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+}
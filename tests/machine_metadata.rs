@@ -0,0 +1,84 @@
+mod common;
+
+use std::fs;
+use std::io::BufReader;
+use git2::Repository;
+use mdbook_git_atom::atom_processor::AtomGenerator;
+use mdbook_git_atom::post_finder::PostFinder;
+use url::Url;
+use common::commit_file;
+
+#[test]
+fn source_path_and_commits_survive_serialization() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/README.md", "# Introduction\n", 0);
+    let created_commit = repo.head().unwrap().target().unwrap().to_string();
+    commit_file(&repo, root, "src/README.md", "# Introduction\n\nMore.\n", 10);
+    let last_commit = repo.head().unwrap().target().unwrap().to_string();
+
+    let post = PostFinder::new(root)
+        .post_for_chapter(root.join("src/README.md"), "Introduction".to_string(), "README.md".into(), None)
+        .unwrap();
+    let feed = AtomGenerator::new()
+        .with_machine_metadata(true)
+        .generate(vec![post], "Test Book".to_string(), Url::parse("https://example.com/book/").unwrap())
+        .unwrap();
+    let xml = feed.to_string();
+    assert!(xml.contains("xmlns:mdbook-git-atom=\"https://github.com/younata/mdbook-git-atom\""), "{}", xml);
+
+    let feed = atom_syndication::Feed::read_from(BufReader::new(xml.as_bytes())).unwrap();
+    let elements = &feed.entries[0].extensions["mdbook-git-atom"];
+    let value = |name: &str| elements[name][0].value.clone();
+    assert_eq!(value("sourcePath").as_deref(), Some("src/README.md"));
+    assert_eq!(value("lastCommit"), Some(last_commit));
+    assert_eq!(value("createdCommit"), Some(created_commit));
+    assert_eq!(value("revisionCount").as_deref(), Some("2"));
+}
+
+#[test]
+fn created_commit_survives_rewriting_every_original_line() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/README.md", "# Introduction\n", 0);
+    let created_commit = repo.head().unwrap().target().unwrap().to_string();
+    // No line blames to the commit that created the chapter any more.
+    commit_file(&repo, root, "src/README.md", "# Getting started\n", 10);
+    let last_commit = repo.head().unwrap().target().unwrap().to_string();
+
+    let post = PostFinder::new(root)
+        .post_for_chapter(root.join("src/README.md"), "Getting started".to_string(), "README.md".into(), None)
+        .unwrap();
+    let feed = AtomGenerator::new()
+        .with_machine_metadata(true)
+        .generate(vec![post], "Test Book".to_string(), Url::parse("https://example.com/book/").unwrap())
+        .unwrap();
+
+    let feed = atom_syndication::Feed::read_from(BufReader::new(feed.to_string().as_bytes())).unwrap();
+    let elements = &feed.entries[0].extensions["mdbook-git-atom"];
+    let value = |name: &str| elements[name][0].value.clone();
+    assert_eq!(value("createdCommit"), Some(created_commit));
+    assert_eq!(value("lastCommit"), Some(last_commit));
+}
+
+#[test]
+fn leaves_out_machine_metadata_by_default() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/README.md", "# Introduction\n", 0);
+
+    let post = PostFinder::new(root)
+        .post_for_chapter(root.join("src/README.md"), "Introduction".to_string(), "README.md".into(), None)
+        .unwrap();
+    let feed = AtomGenerator::new()
+        .generate(vec![post], "Test Book".to_string(), Url::parse("https://example.com/book/").unwrap())
+        .unwrap();
+
+    assert!(!feed.to_string().contains("mdbook-git-atom:lastCommit"));
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file};
+
+// Client-side widgets read posts.json by these names, so renaming any of them breaks those widgets.
+#[test]
+fn posts_json_field_names_are_stable() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n\nHow to use the book.\n", 0);
+
+    atom_feed(root, json!({ "base_url": "https://example.com/book/", "manifest": true }), vec![chapter("Guide", "guide.md", "# Guide\n\nHow to use the book.\n")]);
+    let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(root.join("src/posts.json")).unwrap()).unwrap();
+
+    let entries = manifest.as_array().expect("posts.json should be an array");
+    assert_eq!(entries.len(), 1);
+    let mut field_names: Vec<&str> = entries[0].as_object().unwrap().keys().map(|key| key.as_str()).collect();
+    field_names.sort_unstable();
+    assert_eq!(field_names, ["authors", "id", "published", "section", "summary", "title", "updated", "url"]);
+
+    assert_eq!(entries[0]["title"], "Guide");
+    assert_eq!(entries[0]["url"], "https://example.com/book/guide.html");
+    assert_eq!(entries[0]["published"], "2020-09-13T12:26:40+00:00");
+    assert_eq!(entries[0]["updated"], "2020-09-13T12:26:40+00:00");
+    assert_eq!(entries[0]["authors"], json!(["Alice"]));
+}
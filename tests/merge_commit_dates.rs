@@ -0,0 +1,109 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use git2::{Repository, ResetType, Signature, Time};
+use mdbook::Config;
+use mdbook_git_atom::atom_processor::AtomConfig;
+use mdbook_git_atom::post_finder::{BlameConfig, MergeCommitDates, PostFinder};
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file};
+
+const TRANSLATED: &str = "# Introduction\n\nTranslated.\n";
+
+// The introduction is translated on a branch at 10 minutes, which is merged at 30 minutes after an unrelated change on
+// the main branch at 20 minutes.
+fn branchy_repository(root: &Path) {
+    fs::create_dir_all(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/README.md", "# Introduction\n", 0);
+    let first = repo.head().unwrap().peel_to_commit().unwrap();
+    commit_file(&repo, root, "src/README.md", TRANSLATED, 10);
+    let translation = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.reset(first.as_object(), ResetType::Hard, None).unwrap();
+    commit_file(&repo, root, "src/other.md", "# Other\n", 20);
+    let main = repo.head().unwrap().peel_to_commit().unwrap();
+
+    fs::write(root.join("src/README.md"), TRANSLATED).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("src/README.md")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::new("Merger", "merger@example.com", &Time::new(1_600_000_000 + 30 * 60, 0)).unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "Merge translation", &tree, &[&main, &translation]).unwrap();
+}
+
+fn last_modified_minutes(root: &Path, blame_config: BlameConfig) -> i64 {
+    let post = PostFinder::new(root)
+        .with_blame_config(blame_config)
+        .post_for_chapter(root.join("src/README.md"), "Introduction".to_string(), "README.md".into(), None)
+        .unwrap();
+    (post.last_modified_date().seconds() - 1_600_000_000) / 60
+}
+
+#[test]
+fn dates_merged_lines_by_the_merge_by_default() {
+    let directory = tempfile::tempdir().unwrap();
+    branchy_repository(directory.path());
+
+    assert_eq!(last_modified_minutes(directory.path(), BlameConfig::default()), 30);
+}
+
+#[test]
+fn dates_merged_lines_by_the_commit_that_changed_them_when_skipping_merges() {
+    let directory = tempfile::tempdir().unwrap();
+    branchy_repository(directory.path());
+
+    let blame_config = BlameConfig { merge_commit_dates: MergeCommitDates::Skip, ..BlameConfig::default() };
+    assert_eq!(last_modified_minutes(directory.path(), blame_config), 10);
+}
+
+#[test]
+fn dates_merged_lines_by_the_branch_when_not_following_the_first_parent() {
+    let directory = tempfile::tempdir().unwrap();
+    branchy_repository(directory.path());
+
+    let blame_config = BlameConfig { first_parent: false, ..BlameConfig::default() };
+    assert_eq!(last_modified_minutes(directory.path(), blame_config), 10);
+    let blame_config = BlameConfig { first_parent: false, merge_commit_dates: MergeCommitDates::Skip, ..BlameConfig::default() };
+    assert_eq!(last_modified_minutes(directory.path(), blame_config), 10);
+}
+
+#[test]
+fn skips_merges_in_histories_of_chapters_too_large_to_blame() {
+    let directory = tempfile::tempdir().unwrap();
+    branchy_repository(directory.path());
+
+    let blame_config = BlameConfig { max_file_bytes: 1, ..BlameConfig::default() };
+    assert_eq!(last_modified_minutes(directory.path(), blame_config), 30);
+    let blame_config = BlameConfig { max_file_bytes: 1, merge_commit_dates: MergeCommitDates::Skip, ..BlameConfig::default() };
+    assert_eq!(last_modified_minutes(directory.path(), blame_config), 10);
+}
+
+#[test]
+fn reads_first_parent_from_the_config() {
+    let directory = tempfile::tempdir().unwrap();
+    branchy_repository(directory.path());
+    let sections = || vec![chapter("Introduction", "README.md", TRANSLATED)];
+
+    let xml = atom_feed(directory.path(), json!({ "base_url": "https://example.com/book" }), sections());
+    assert!(xml.contains("<updated>2020-09-13T12:56:40"), "{}", xml);
+    let xml = atom_feed(directory.path(), json!({ "base_url": "https://example.com/book", "first_parent": false }), sections());
+    assert!(xml.contains("<updated>2020-09-13T12:36:40"), "{}", xml);
+    // The older name still works.
+    let xml = atom_feed(directory.path(), json!({ "base_url": "https://example.com/book", "blame_first_parent": false }), sections());
+    assert!(xml.contains("<updated>2020-09-13T12:36:40"), "{}", xml);
+}
+
+#[test]
+fn rejects_a_first_parent_that_isnt_a_boolean() {
+    let directory = tempfile::tempdir().unwrap();
+    let section: toml::value::Table = toml::from_str(r#"
+        base_url = "https://example.com/book"
+        first_parent = "no"
+    "#).unwrap();
+
+    let error = AtomConfig::from_section(directory.path(), &Config::default(), &section).err().expect("first_parent has to be a boolean");
+
+    assert!(error.to_string().contains("Expected true or false"), "{}", error);
+}
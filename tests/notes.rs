@@ -0,0 +1,72 @@
+mod common;
+
+use std::fs;
+use git2::{Repository, Signature, Time};
+use mdbook_git_atom::post_finder::PostFinder;
+use common::commit_file;
+
+const NOTES_REF: &str = "refs/notes/publish-dates";
+
+fn add_note(repo: &Repository, commit: git2::Oid, message: &str) {
+    let signature = Signature::new("Editor", "editor@example.com", &Time::new(1_700_000_000, 0)).unwrap();
+    repo.note(&signature, &signature, Some(NOTES_REF), commit, message, false).unwrap();
+}
+
+#[test]
+fn dates_chapters_by_the_note_on_the_commit_that_created_them() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/post.md", "# Post\n\nFirst draft.\n", 0);
+    let creating_commit = repo.head().unwrap().target().unwrap();
+    // Rewriting the first line means the first blame hunk is no longer from the creating commit.
+    commit_file(&repo, root, "src/post.md", "# Final post\n\nFirst draft.\n", 10);
+    add_note(&repo, creating_commit, "2024-05-01T09:00:00Z");
+
+    let post = PostFinder::new(root)
+        .with_notes_ref(Some(NOTES_REF.to_string()))
+        .post_for_chapter(root.join("src/post.md"), "Post".to_string(), "post.md".into(), None)
+        .unwrap();
+
+    assert_eq!(post.created_date().seconds(), 1_714_554_000);
+    assert_eq!(post.last_modified_date().seconds(), 1_600_000_000 + 10 * 60);
+}
+
+#[test]
+fn ignores_notes_without_a_date() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/post.md", "# Post\n", 0);
+    add_note(&repo, repo.head().unwrap().target().unwrap(), "Published after review");
+
+    let post = PostFinder::new(root)
+        .with_notes_ref(Some(NOTES_REF.to_string()))
+        .post_for_chapter(root.join("src/post.md"), "Post".to_string(), "post.md".into(), None)
+        .unwrap();
+
+    assert_eq!(post.created_date().seconds(), 1_600_000_000);
+}
+
+#[test]
+fn dates_empty_chapters_by_their_commits() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/empty.md", "", 0);
+    add_note(&repo, repo.head().unwrap().target().unwrap(), "2024-05-01T09:00:00Z");
+    commit_file(&repo, root, "src/other.md", "# Other\n", 10);
+
+    // Empty files have no lines to blame.
+    let post = PostFinder::new(root)
+        .with_notes_ref(Some(NOTES_REF.to_string()))
+        .post_for_chapter(root.join("src/empty.md"), "Empty".to_string(), "empty.md".into(), None)
+        .unwrap();
+
+    assert_eq!(post.created_date().seconds(), 1_714_554_000);
+    assert_eq!(post.last_modified_date().seconds(), 1_600_000_000);
+    assert!(post.is_git_tracked());
+}
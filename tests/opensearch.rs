@@ -0,0 +1,28 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file};
+
+#[test]
+fn describes_the_books_search_page() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 0);
+
+    let options = json!({ "base_url": "https://example.com/book", "generate_opensearch": true });
+    atom_feed(root, options, vec![chapter("Guide", "guide.md", "# Guide\n")]);
+
+    assert_eq!(fs::read_to_string(root.join("src/opensearch.xml")).unwrap(), concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<OpenSearchDescription xmlns=\"http://a9.com/-/spec/opensearch/1.1/\">\n",
+        "  <ShortName>Test Book</ShortName>\n",
+        "  <Description>Search Test Book</Description>\n",
+        "  <InputEncoding>UTF-8</InputEncoding>\n",
+        "  <Url type=\"text/html\" template=\"https://example.com/book/search.html?query={searchTerms}\"/>\n",
+        "</OpenSearchDescription>\n",
+    ));
+}
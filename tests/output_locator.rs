@@ -0,0 +1,37 @@
+use std::path::Path;
+use mdbook::Config;
+use mdbook_git_atom::output_locator::{Destination, OutputLocator};
+
+fn locator(book_toml: &str) -> OutputLocator {
+    OutputLocator::new(Path::new("/books/guide"), &book_toml.parse::<Config>().unwrap())
+}
+
+#[test]
+fn default_layout_writes_to_src_and_the_book_directory() {
+    let locator = locator("[book]\ntitle = \"Guide\"\n");
+
+    assert_eq!(locator.src_dir(), Path::new("/books/guide/src"));
+    assert_eq!(locator.build_dir(), Path::new("/books/guide/book"));
+    assert_eq!(locator.html_dir(), Path::new("/books/guide/book"));
+    assert_eq!(locator.locate(Destination::Src, "atom.xml"), Path::new("/books/guide/src/atom.xml"));
+    assert_eq!(locator.locate(Destination::Build, "atom.xml"), Path::new("/books/guide/book/atom.xml"));
+}
+
+#[test]
+fn custom_build_dir_and_src_are_honored() {
+    let locator = locator("[book]\nsrc = \"content\"\n\n[build]\nbuild-dir = \"public\"\n\n[output.html]\n");
+
+    assert_eq!(locator.src_dir(), Path::new("/books/guide/content"));
+    assert_eq!(locator.build_dir(), Path::new("/books/guide/public"));
+    assert_eq!(locator.locate(Destination::Build, "en/atom.xml"), Path::new("/books/guide/public/en/atom.xml"));
+}
+
+#[test]
+fn several_renderers_put_html_output_in_its_own_directory() {
+    let locator = locator("[build]\nbuild-dir = \"public\"\n\n[output.html]\n\n[output.markdown]\n");
+
+    assert_eq!(locator.build_dir(), Path::new("/books/guide/public"));
+    assert_eq!(locator.html_dir(), Path::new("/books/guide/public/html"));
+    assert_eq!(locator.locate(Destination::Build, "posts.json"), Path::new("/books/guide/public/html/posts.json"));
+    assert_eq!(locator.locate(Destination::Src, "posts.json"), Path::new("/books/guide/src/posts.json"));
+}
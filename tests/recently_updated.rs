@@ -0,0 +1,272 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use mdbook::preprocess::{CmdPreprocessor, Preprocessor};
+use mdbook_git_atom::updated_processor::UpdatedProcessor;
+use serde_json::json;
+use common::{chapter, commit_at_offset, commit_file, CURRENT_DIRECTORY};
+
+const CHAPTER: &str = "# Introduction
+
+Before the lists.
+{{#recently_updated}}
+Between the *first* and second lists.
+
+{{#recently_updated count=3}}
+  Indented text between the second and third lists.
+{{#recently_updated count=10}}
+After the lists.
+";
+
+fn expected_list(numbers: std::ops::RangeInclusive<usize>) -> String {
+    numbers.rev().map(|number| format!("- Chapter {}\n", number)).collect()
+}
+
+#[test]
+fn replaces_every_recently_updated_directive_and_preserves_the_text_between_them() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+
+    commit_file(&repo, root, "src/README.md", CHAPTER, 0);
+    let mut sections = vec![chapter("Introduction", "README.md", CHAPTER)];
+    for number in 1..=12 {
+        let path = format!("chapter_{}.md", number);
+        let content = format!("# Chapter {}\n", number);
+        commit_file(&repo, root, &format!("src/{}", path), &content, number as i64);
+        sections.push(chapter(&format!("Chapter {}", number), &path, &content));
+    }
+
+    let input = json!([
+        {
+            "root": root,
+            "config": {
+                "book": { "title": "Test Book", "src": "src" },
+                "preprocessor": {
+                    "git-updated": { "target_number_of_entries": 5, "template": "- {title}" },
+                },
+            },
+            "renderer": "html",
+            "mdbook_version": "0.4.21",
+        },
+        { "sections": sections, "__non_exhaustive": null },
+    ]);
+    let (ctx, book) = CmdPreprocessor::parse_input(input.to_string().as_bytes()).unwrap();
+
+    // Chapter sources are read relative to the book root, the same as when mdbook runs the preprocessor.
+    let _guard = CURRENT_DIRECTORY.lock().unwrap_or_else(|error| error.into_inner());
+    std::env::set_current_dir(root).unwrap();
+    let book = UpdatedProcessor::new().run(&ctx, book).unwrap();
+
+    let content = match &book.sections[0] {
+        mdbook::BookItem::Chapter(chapter) => chapter.content.to_string(),
+        _ => panic!("The first section should be the introduction"),
+    };
+    let expected = format!(
+        "# Introduction
+
+Before the lists.
+{}
+Between the *first* and second lists.
+
+{}
+  Indented text between the second and third lists.
+{}
+After the lists.
+",
+        expected_list(8..=12),
+        expected_list(10..=12),
+        expected_list(3..=12),
+    );
+    assert_eq!(content, expected);
+}
+
+#[test]
+fn shows_dates_in_the_configured_timezone() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+
+    // 23:50 UTC on 2020-09-13, which is already the 14th in Tokyo, where it was committed.
+    let content = "# Late\n\n{{#last_modified}}\n";
+    commit_at_offset(&repo, root, "src/late.md", content.as_bytes(), 684, 9 * 60);
+
+    let last_modified = |timezone: Option<&str>| {
+        let options = match timezone {
+            Some(timezone) => json!({ "timezone": timezone }),
+            None => json!({}),
+        };
+        let input = json!([
+            {
+                "root": root,
+                "config": {
+                    "book": { "title": "Test Book", "src": "src" },
+                    "preprocessor": { "git-updated": options },
+                },
+                "renderer": "html",
+                "mdbook_version": "0.4.21",
+            },
+            { "sections": [chapter("Late", "late.md", content)], "__non_exhaustive": null },
+        ]);
+        let (ctx, book) = CmdPreprocessor::parse_input(input.to_string().as_bytes()).unwrap();
+        let book = UpdatedProcessor::new().run(&ctx, book).unwrap();
+        match &book.sections[0] {
+            mdbook::BookItem::Chapter(chapter) => chapter.content.lines().last().unwrap().to_string(),
+            _ => panic!("The only section should be the chapter"),
+        }
+    };
+
+    let _guard = CURRENT_DIRECTORY.lock().unwrap_or_else(|error| error.into_inner());
+    std::env::set_current_dir(root).unwrap();
+    assert_eq!(last_modified(None), "2020-09-14");
+    assert_eq!(last_modified(Some("UTC")), "2020-09-13");
+    assert_eq!(last_modified(Some("-05:00")), "2020-09-13");
+    assert_eq!(last_modified(Some("+09:00")), "2020-09-14");
+}
+
+#[test]
+fn replaces_recently_updated_directives_in_the_middle_of_a_line() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+
+    // Conditionals handled by another preprocessor are left for it, along with the text around the directive.
+    let content = "# Introduction\n\nUpdated pages: {{#recently_updated count=3}} and more.\n{{#if show_updates}}{{#recently_updated count=1}}{{/if}}\n";
+    commit_file(&repo, root, "src/README.md", content, 0);
+    let mut sections = vec![chapter("Introduction", "README.md", content)];
+    for number in 1..=5 {
+        let path = format!("chapter_{}.md", number);
+        let chapter_content = format!("# Chapter {}\n", number);
+        commit_file(&repo, root, &format!("src/{}", path), &chapter_content, number as i64);
+        sections.push(chapter(&format!("Chapter {}", number), &path, &chapter_content));
+    }
+
+    let input = json!([
+        {
+            "root": root,
+            "config": {
+                "book": { "title": "Test Book", "src": "src" },
+                "preprocessor": {
+                    "git-updated": { "template": "- {title}" },
+                },
+            },
+            "renderer": "html",
+            "mdbook_version": "0.4.21",
+        },
+        { "sections": sections, "__non_exhaustive": null },
+    ]);
+    let (ctx, book) = CmdPreprocessor::parse_input(input.to_string().as_bytes()).unwrap();
+
+    let _guard = CURRENT_DIRECTORY.lock().unwrap_or_else(|error| error.into_inner());
+    std::env::set_current_dir(root).unwrap();
+    let book = UpdatedProcessor::new().run(&ctx, book).unwrap();
+
+    let content = match &book.sections[0] {
+        mdbook::BookItem::Chapter(chapter) => chapter.content.to_string(),
+        _ => panic!("The first section should be the introduction"),
+    };
+    let expected = format!(
+        "# Introduction\n\nUpdated pages: {} and more.\n{{{{#if show_updates}}}}{}{{{{/if}}}}\n",
+        expected_list(3..=5),
+        expected_list(5..=5),
+    );
+    assert_eq!(content, expected);
+}
+
+#[test]
+fn rolls_up_nested_chapters_like_the_feed() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src/releases")).unwrap();
+    let repo = Repository::init(root).unwrap();
+
+    let content = "# Introduction\n\n{{#recently_updated}}\n";
+    commit_file(&repo, root, "src/README.md", content, 0);
+    commit_file(&repo, root, "src/releases.md", "# Releases\n", 1);
+    commit_file(&repo, root, "src/releases/1.0.md", "# 1.0\n", 2);
+    let mut releases = chapter("Releases", "releases.md", "# Releases\n");
+    releases["Chapter"]["sub_items"] = json!([chapter("1.0", "releases/1.0.md", "# 1.0\n")]);
+
+    let input = json!([
+        {
+            "root": root,
+            "config": {
+                "book": { "title": "Test Book", "src": "src" },
+                "preprocessor": {
+                    "git-updated": { "template": "- {title}", "rollup_depth": 1 },
+                },
+            },
+            "renderer": "html",
+            "mdbook_version": "0.4.21",
+        },
+        { "sections": [chapter("Introduction", "README.md", content), releases], "__non_exhaustive": null },
+    ]);
+    let (ctx, book) = CmdPreprocessor::parse_input(input.to_string().as_bytes()).unwrap();
+
+    let _guard = CURRENT_DIRECTORY.lock().unwrap_or_else(|error| error.into_inner());
+    std::env::set_current_dir(root).unwrap();
+    let book = UpdatedProcessor::new().run(&ctx, book).unwrap();
+
+    let content = match &book.sections[0] {
+        mdbook::BookItem::Chapter(chapter) => chapter.content.to_string(),
+        _ => panic!("The first section should be the introduction"),
+    };
+    // The release is listed as its parent, updated when the release was.
+    assert_eq!(content, "# Introduction\n\n- Releases\n- Introduction\n\n");
+}
+
+#[test]
+fn escapes_markdown_in_titles_so_links_still_work() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+
+    let content = "# Introduction\n\n{{#recently_updated count=1}}\n";
+    commit_file(&repo, root, "src/README.md", content, 0);
+    let title = "Using ] and [ with * and _";
+    commit_file(&repo, root, "src/brackets.md", "# Brackets\n", 1);
+
+    let input = json!([
+        {
+            "root": root,
+            "config": {
+                "book": { "title": "Test Book", "src": "src" },
+                "preprocessor": { "git-updated": {} },
+            },
+            "renderer": "html",
+            "mdbook_version": "0.4.21",
+        },
+        { "sections": [chapter("Introduction", "README.md", content), chapter(title, "brackets.md", "# Brackets\n")], "__non_exhaustive": null },
+    ]);
+    let (ctx, book) = CmdPreprocessor::parse_input(input.to_string().as_bytes()).unwrap();
+
+    let _guard = CURRENT_DIRECTORY.lock().unwrap_or_else(|error| error.into_inner());
+    std::env::set_current_dir(root).unwrap();
+    let book = UpdatedProcessor::new().run(&ctx, book).unwrap();
+
+    let content = match &book.sections[0] {
+        mdbook::BookItem::Chapter(chapter) => chapter.content.to_string(),
+        _ => panic!("The first section should be the introduction"),
+    };
+    // The whole title is the text of a single link to the chapter.
+    let mut links = vec![];
+    let mut link: Option<(String, String)> = None;
+    for event in pulldown_cmark::Parser::new(&content) {
+        match event {
+            pulldown_cmark::Event::Start(pulldown_cmark::Tag::Link(_, url, _)) => link = Some((url.to_string(), String::new())),
+            pulldown_cmark::Event::Text(text) => {
+                if let Some((_, link_text)) = link.as_mut() {
+                    link_text.push_str(&text);
+                }
+            }
+            pulldown_cmark::Event::End(pulldown_cmark::Tag::Link(..)) => links.extend(link.take()),
+            _ => (),
+        }
+    }
+    assert_eq!(links, vec![("/brackets.html".to_string(), title.to_string())], "{}", content);
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use git2::{Repository, Signature, Time};
+use mdbook_git_atom::post_finder::{BlameConfig, PostFinder};
+use common::commit_file;
+
+// Moves a committed file to a new path, keeping its content, in a commit the given number of minutes after the first.
+fn commit_rename(repo: &Repository, root: &Path, from: &str, to: &str, minutes: i64) {
+    fs::rename(root.join(from), root.join(to)).unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.remove_path(Path::new(from)).unwrap();
+    index.add_path(Path::new(to)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+    let signature = Signature::new("Alice", "alice@example.com", &Time::new(1_600_000_000 + minutes * 60, 0)).unwrap();
+    let parent = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, to, &tree, &[&parent]).unwrap();
+}
+
+fn renamed_chapter() -> tempfile::TempDir {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/draft.md", "# Guide\n\nFirst.\n", 0);
+    commit_file(&repo, root, "src/draft.md", "# Guide\n\nFirst.\n\nSecond.\n", 10);
+    commit_rename(&repo, root, "src/draft.md", "src/guide.md", 20);
+    directory
+}
+
+fn revision_count(root: &Path, blame_config: BlameConfig) -> u32 {
+    PostFinder::new(root)
+        .with_blame_config(blame_config)
+        .post_for_chapter(root.join("src/guide.md"), "Guide".to_string(), "guide.md".into(), None)
+        .unwrap()
+        .revision_count()
+}
+
+#[test]
+fn counts_revisions_since_the_rename_by_default() {
+    let directory = renamed_chapter();
+
+    assert_eq!(revision_count(directory.path(), BlameConfig::default()), 1);
+}
+
+#[test]
+fn counts_revisions_before_the_rename_when_following_renames() {
+    let directory = renamed_chapter();
+    let blame_config = BlameConfig { follow_renames: true, ..BlameConfig::default() };
+
+    assert_eq!(revision_count(directory.path(), blame_config), 3);
+}
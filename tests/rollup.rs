@@ -0,0 +1,71 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use mdbook::book::Book;
+use mdbook_git_atom::post_finder::{PostFinder, SortDirection, SortField};
+use serde_json::json;
+use common::{chapter, commit_file};
+
+// A version page, which is a draft when its path is None, with two release notes under it.
+fn book_with_version_page(path: Option<&str>) -> Book {
+    let mut notes = vec![chapter("Feature A", "v2/feature-a.md", "# Feature A\n"), chapter("Feature B", "v2/feature-b.md", "# Feature B\n")];
+    for note in notes.iter_mut() {
+        note["Chapter"]["parent_names"] = json!(["Version 2"]);
+    }
+    serde_json::from_value(json!({
+        "sections": [
+            chapter("Introduction", "README.md", "# Introduction\n"),
+            {
+                "Chapter": {
+                    "name": "Version 2",
+                    "content": "# Version 2\n",
+                    "number": null,
+                    "sub_items": notes,
+                    "path": path,
+                    "source_path": path,
+                    "parent_names": [],
+                }
+            },
+        ],
+        "__non_exhaustive": null,
+    })).unwrap()
+}
+
+fn post_paths(book: &Book, root: &std::path::Path) -> Vec<String> {
+    let posts = PostFinder::new(root)
+        .with_rollup_depth(1)
+        .search(book, &root.join("src"), None, 0, SortField::LastModified, SortDirection::Descending)
+        .unwrap();
+    posts.iter().map(|post| post.path().to_string_lossy().to_string()).collect()
+}
+
+fn repository() -> tempfile::TempDir {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src/v2")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/README.md", "# Introduction\n", 0);
+    commit_file(&repo, root, "src/v2/index.md", "# Version 2\n", 1);
+    commit_file(&repo, root, "src/v2/feature-a.md", "# Feature A\n", 2);
+    commit_file(&repo, root, "src/v2/feature-b.md", "# Feature B\n", 3);
+    directory
+}
+
+#[test]
+fn rolls_descendants_up_into_their_ancestor() {
+    let directory = repository();
+
+    let paths = post_paths(&book_with_version_page(Some("v2/index.md")), directory.path());
+
+    assert_eq!(paths, ["v2/index.md", "README.md"]);
+}
+
+#[test]
+fn leaves_out_descendants_of_a_draft_ancestor() {
+    let directory = repository();
+
+    let paths = post_paths(&book_with_version_page(None), directory.path());
+
+    assert_eq!(paths, ["README.md"]);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use mdbook::book::Book;
+use mdbook_git_atom::post_finder::{skip_summary, PostFinder, SkipReason};
+use serde_json::json;
+use common::{chapter, commit_file};
+
+fn book_with_every_kind_of_chapter() -> Book {
+    let draft = json!({
+        "Chapter": {
+            "name": "Draft",
+            "content": "",
+            "number": null,
+            "sub_items": [],
+            "path": null,
+            "source_path": null,
+            "parent_names": [],
+        }
+    });
+    let synthesized = json!({
+        "Chapter": {
+            "name": "Generated",
+            "content": "",
+            "number": null,
+            "sub_items": [],
+            "path": "generated.md",
+            "source_path": null,
+            "parent_names": [],
+        }
+    });
+    serde_json::from_value(json!({
+        "sections": [
+            chapter("Introduction", "README.md", "# Introduction\n"),
+            draft,
+            chapter("Summary", "SUMMARY.md", "# Summary\n"),
+            chapter("Missing", "missing.md", "# Missing\n"),
+            chapter("Uncommitted", "uncommitted.md", "# Uncommitted\n"),
+            synthesized,
+        ],
+        "__non_exhaustive": null,
+    })).unwrap()
+}
+
+#[test]
+fn reports_why_each_chapter_was_skipped() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/README.md", "# Introduction\n", 0);
+    commit_file(&repo, root, "src/SUMMARY.md", "# Summary\n", 1);
+    // Untracked chapters are still posts, dated by the file.
+    fs::write(root.join("src/uncommitted.md"), "# Uncommitted\n").unwrap();
+
+    let book = book_with_every_kind_of_chapter();
+    let skipped = PostFinder::new(root).skipped_chapters(&book, &root.join("src"));
+
+    assert_eq!(skipped, vec![
+        ("Draft".to_string(), SkipReason::Draft),
+        ("Summary".to_string(), SkipReason::Excluded),
+        ("Missing".to_string(), SkipReason::NoHistory),
+        ("Generated".to_string(), SkipReason::Synthesized),
+    ]);
+    assert_eq!(skip_summary(&skipped), vec![
+        "Skipped 1 draft chapter(s): Draft",
+        "Skipped 1 excluded chapter(s): Summary",
+        "Skipped 1 chapter(s) without git history: Missing",
+        "Skipped 1 chapter(s) without a source file: Generated",
+    ]);
+}
+
+#[test]
+fn summarizes_nothing_when_no_chapters_were_skipped() {
+    assert!(skip_summary(&[]).is_empty());
+}
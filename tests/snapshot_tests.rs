@@ -0,0 +1,56 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use mdbook::preprocess::{CmdPreprocessor, Preprocessor};
+use mdbook_git_atom::atom_processor::AtomProcessor;
+use serde_json::json;
+use common::{chapter, commit_file};
+
+const INTRODUCTION: &str = "# Introduction
+
+Welcome to the *book*.
+";
+
+const INSTALLING: &str = "# Installing
+
+Run `cargo install mdbook-git-atom`.
+
+![Screenshot](screenshot.png)
+";
+
+#[test]
+fn atom_xml() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+
+    commit_file(&repo, root, "src/README.md", INTRODUCTION, 0);
+    commit_file(&repo, root, "src/installing.md", INSTALLING, 1);
+
+    let input = json!([
+        {
+            "root": root,
+            "config": {
+                "book": { "title": "Test Book", "src": "src", "language": "en" },
+                "preprocessor": {
+                    "git-atom": { "base_url": "https://example.com/book/", "preview_lines": -1 },
+                },
+            },
+            "renderer": "html",
+            "mdbook_version": "0.4.21",
+        },
+        {
+            "sections": [chapter("Introduction", "README.md", INTRODUCTION), chapter("Installing", "installing.md", INSTALLING)],
+            "__non_exhaustive": null,
+        },
+    ]);
+    let (ctx, book) = CmdPreprocessor::parse_input(input.to_string().as_bytes()).unwrap();
+
+    // The feed is written relative to the book root, the same as when mdbook runs the preprocessor.
+    std::env::set_current_dir(root).unwrap();
+    AtomProcessor::new().run(&ctx, book).unwrap();
+
+    insta::assert_snapshot!(fs::read_to_string(root.join("src/atom.xml")).unwrap());
+}
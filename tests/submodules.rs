@@ -0,0 +1,62 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use git2::Repository;
+use mdbook_git_atom::post_finder::PostFinder;
+use common::commit_file;
+
+#[test]
+fn dates_chapters_in_a_submodule_by_the_submodule_history() {
+    let directory = tempfile::tempdir().unwrap();
+    let shared_root = directory.path().join("docs-common");
+    fs::create_dir_all(&shared_root).unwrap();
+    let shared_repo = Repository::init(&shared_root).unwrap();
+    commit_file(&shared_repo, &shared_root, "page.md", "# Shared page\n", 100);
+
+    let root = directory.path().join("book");
+    fs::create_dir_all(root.join("src")).unwrap();
+    let repo = Repository::init(&root).unwrap();
+    commit_file(&repo, &root, "src/README.md", "# Introduction\n", 0);
+    let mut submodule = repo.submodule(shared_root.to_str().unwrap(), Path::new("src/shared"), true).unwrap();
+    submodule.clone(None).unwrap();
+    submodule.add_finalize().unwrap();
+    commit_file(&repo, &root, ".gitmodules", &fs::read_to_string(root.join(".gitmodules")).unwrap(), 200);
+
+    let post = PostFinder::new(&root)
+        .post_for_chapter(root.join("src/shared/page.md"), "Shared page".to_string(), "shared/page.md".into(), None)
+        .expect("Chapters in submodules should have posts");
+
+    // The superproject only knows when the submodule was added, at 200 minutes.
+    assert_eq!(post.last_modified_date().seconds(), 1_600_000_000 + 100 * 60);
+    assert_eq!(post.created_date().seconds(), 1_600_000_000 + 100 * 60);
+    assert!(post.is_git_tracked());
+    assert_eq!(post.authors().keys().map(|author| author.name()).collect::<Vec<&str>>(), vec!["Alice"]);
+}
+
+#[test]
+fn dates_chapters_the_submodule_cannot_blame_by_the_filesystem() {
+    let directory = tempfile::tempdir().unwrap();
+    let shared_root = directory.path().join("docs-common");
+    fs::create_dir_all(&shared_root).unwrap();
+    let shared_repo = Repository::init(&shared_root).unwrap();
+    commit_file(&shared_repo, &shared_root, "page.md", "# Shared page\n", 100);
+
+    let root = directory.path().join("book");
+    fs::create_dir_all(root.join("src")).unwrap();
+    let repo = Repository::init(&root).unwrap();
+    commit_file(&repo, &root, "src/README.md", "# Introduction\n", 0);
+    let mut submodule = repo.submodule(shared_root.to_str().unwrap(), Path::new("src/shared"), true).unwrap();
+    submodule.clone(None).unwrap();
+    submodule.add_finalize().unwrap();
+    commit_file(&repo, &root, ".gitmodules", &fs::read_to_string(root.join(".gitmodules")).unwrap(), 200);
+    // Not committed in the submodule, so it has no history there to blame.
+    fs::write(root.join("src/shared/draft.md"), "# Draft\n").unwrap();
+
+    let post = PostFinder::new(&root)
+        .post_for_chapter(root.join("src/shared/draft.md"), "Draft".to_string(), "shared/draft.md".into(), None)
+        .expect("Chapters the submodule can't blame should still have posts");
+
+    assert!(post.last_modified_date().seconds() > 1_600_000_000 + 200 * 60);
+    assert!(!post.is_git_tracked());
+}
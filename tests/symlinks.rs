@@ -0,0 +1,45 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use git2::{Repository, Signature, Time};
+use mdbook_git_atom::post_finder::PostFinder;
+use common::commit_file;
+
+// Commits a symlink at path to target, the given number of minutes after the first commit.
+fn commit_symlink(repo: &Repository, root: &Path, path: &str, target: &str, minutes: i64) {
+    std::os::unix::fs::symlink(target, root.join(path)).unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(path)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+    let signature = Signature::new("Bob", "bob@example.com", &Time::new(1_600_000_000 + minutes * 60, 0)).unwrap();
+    let parent = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, path, &tree, &[&parent]).unwrap();
+}
+
+#[test]
+fn blames_the_file_a_symlinked_chapter_links_to() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("shared")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "shared/page.md", "# Shared page\n", 0);
+    commit_file(&repo, root, "shared/page.md", "# Shared page\n\nWith more.\n", 10);
+    commit_symlink(&repo, root, "src/page.md", "../shared/page.md", 100);
+
+    let post = PostFinder::new(root)
+        .post_for_chapter(root.join("src/page.md"), "Shared page".to_string(), "page.md".into(), Some(-1))
+        .expect("Symlinked chapters should have posts");
+
+    // The symlink was committed by Bob at 100 minutes, but the page itself was only ever written by Alice.
+    assert_eq!(post.created_date().seconds(), 1_600_000_000);
+    assert_eq!(post.last_modified_date().seconds(), 1_600_000_000 + 10 * 60);
+    assert_eq!(post.authors().keys().map(|author| author.name()).collect::<Vec<&str>>(), vec!["Alice"]);
+    assert_eq!(post.content(), Some("<p>With more.</p>\n"));
+}
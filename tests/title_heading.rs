@@ -0,0 +1,40 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use mdbook_git_atom::post_finder::PostFinder;
+use common::commit_file;
+
+fn content(markdown: &str, title: &str) -> String {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/chapter.md", markdown, 0);
+
+    let post = PostFinder::new(root)
+        .post_for_chapter(root.join("src/chapter.md"), title.to_string(), "chapter.md".into(), Some(-1))
+        .unwrap();
+    post.content().unwrap().to_string()
+}
+
+#[test]
+fn leaves_out_a_leading_heading_of_the_title() {
+    assert_eq!(content("# My Title\n\nBody text\n", "My Title"), "<p>Body text</p>\n");
+}
+
+#[test]
+fn keeps_a_leading_heading_of_something_other_than_the_title() {
+    assert_eq!(content("# Overview\n\nBody text\n", "My Title"), "<h1>Overview</h1>\n<p>Body text</p>\n");
+}
+
+#[test]
+fn keeps_headings_in_code_blocks() {
+    let markdown = "Run this:\n\n```sh\n# My Title\necho hello\n```\n";
+    assert_eq!(content(markdown, "My Title"), "<p>Run this:</p>\n<pre><code class=\"language-sh\"># My Title\necho hello\n</code></pre>\n");
+}
+
+#[test]
+fn keeps_headings_of_the_title_after_the_first_block() {
+    assert_eq!(content("Introduction\n\n# My Title\n", "My Title"), "<p>Introduction</p>\n<h1>My Title</h1>\n");
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+use git2::Repository;
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file};
+
+fn tombstones(xml: &str) -> Vec<(String, String)> {
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+    feed.extensions.get("at")
+        .and_then(|extensions| extensions.get("deleted-entry"))
+        .map(|tombstones| {
+            tombstones.iter()
+                .map(|tombstone| (tombstone.attrs["ref"].to_string(), tombstone.attrs["when"].to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn entry_ids(xml: &str) -> Vec<String> {
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+    feed.entries.iter().map(|entry| entry.id.to_string()).collect()
+}
+
+#[test]
+fn tombstones_removed_chapters_until_they_are_recreated() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 0);
+    commit_file(&repo, root, "src/old.md", "# Old\n", 1);
+    let options = json!({ "base_url": "https://example.com/book/", "tombstones": true });
+    let guide = || chapter("Guide", "guide.md", "# Guide\n");
+    let old = || chapter("Old", "old.md", "# Old\n");
+
+    let xml = atom_feed(root, options.clone(), vec![guide(), old()]);
+    assert!(tombstones(&xml).is_empty());
+    let old_id = entry_ids(&xml).into_iter().find(|id| id.contains("old")).unwrap();
+
+    // Deleted as of the newest commit.
+    commit_file(&repo, root, "src/guide.md", "# Guide\n\nMore.\n", 10);
+    let xml = atom_feed(root, options.clone(), vec![guide()]);
+    assert_eq!(tombstones(&xml), vec![(old_id.clone(), "2020-09-13T12:36:40+00:00".to_string())]);
+
+    // The tombstone is kept from the previous feed, without its deletion time moving.
+    commit_file(&repo, root, "src/guide.md", "# Guide\n\nEven more.\n", 20);
+    let xml = atom_feed(root, options.clone(), vec![guide()]);
+    assert_eq!(tombstones(&xml), vec![(old_id.clone(), "2020-09-13T12:36:40+00:00".to_string())]);
+
+    // A recreated chapter is an entry again, not a deleted one.
+    let xml = atom_feed(root, options.clone(), vec![guide(), old()]);
+    assert!(tombstones(&xml).is_empty());
+    assert!(entry_ids(&xml).contains(&old_id));
+
+    // And deleting it again tombstones it again.
+    commit_file(&repo, root, "src/guide.md", "# Guide\n\nThe most.\n", 30);
+    let xml = atom_feed(root, options, vec![guide()]);
+    assert_eq!(tombstones(&xml), vec![(old_id, "2020-09-13T12:56:40+00:00".to_string())]);
+}
+
+#[test]
+fn forgets_tombstones_older_than_the_maximum_age() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 0);
+    commit_file(&repo, root, "src/old.md", "# Old\n", 1);
+    let options = json!({ "base_url": "https://example.com/book/", "tombstones": true, "tombstone_max_age_days": 1 });
+    let guide = || chapter("Guide", "guide.md", "# Guide\n");
+
+    atom_feed(root, options.clone(), vec![guide(), chapter("Old", "old.md", "# Old\n")]);
+    let xml = atom_feed(root, options.clone(), vec![guide()]);
+    assert_eq!(tombstones(&xml).len(), 1);
+
+    // Two days later.
+    commit_file(&repo, root, "src/guide.md", "# Guide\n\nMore.\n", 2 * 24 * 60);
+    let xml = atom_feed(root, options, vec![guide()]);
+    assert!(tombstones(&xml).is_empty());
+}
+
+#[test]
+fn leaves_out_tombstones_by_default() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 0);
+    commit_file(&repo, root, "src/old.md", "# Old\n", 1);
+    let options = json!({ "base_url": "https://example.com/book/" });
+
+    atom_feed(root, options.clone(), vec![chapter("Guide", "guide.md", "# Guide\n"), chapter("Old", "old.md", "# Old\n")]);
+    let xml = atom_feed(root, options, vec![chapter("Guide", "guide.md", "# Guide\n")]);
+    assert!(tombstones(&xml).is_empty());
+}
+
+#[test]
+fn keeps_merged_entries_alive() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 0);
+    fs::write(root.join("blog.xml"), concat!(
+        "<?xml version=\"1.0\"?>\n",
+        "<feed xmlns=\"http://www.w3.org/2005/Atom\">",
+        "<title>Blog</title><id>https://example.com/blog/</id><updated>2020-09-13T12:30:00+00:00</updated>",
+        "<entry><title>Announcement</title><id>https://example.com/blog/announcement</id>",
+        "<updated>2020-09-13T12:30:00+00:00</updated></entry>",
+        "</feed>",
+    )).unwrap();
+    let options = json!({ "base_url": "https://example.com/book/", "tombstones": true, "merge_feeds": ["blog.xml"] });
+
+    atom_feed(root, options.clone(), vec![chapter("Guide", "guide.md", "# Guide\n")]);
+    let xml = atom_feed(root, options, vec![chapter("Guide", "guide.md", "# Guide\n")]);
+
+    assert!(tombstones(&xml).is_empty(), "{}", xml);
+    assert!(entry_ids(&xml).contains(&"https://example.com/blog/announcement".to_string()));
+}
+
+#[test]
+fn keeps_chapters_too_old_for_the_feed_alive() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/old.md", "# Old\n", 0);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", (now - 1_600_000_000) / 60);
+    let sections = || vec![chapter("Guide", "guide.md", "# Guide\n"), chapter("Old", "old.md", "# Old\n")];
+
+    let xml = atom_feed(root, json!({ "base_url": "https://example.com/book/", "tombstones": true }), sections());
+    assert_eq!(entry_ids(&xml).len(), 2);
+    let options = json!({ "base_url": "https://example.com/book/", "tombstones": true, "max_entry_age_days": 30 });
+    let xml = atom_feed(root, options, sections());
+
+    assert_eq!(entry_ids(&xml).len(), 1);
+    assert!(tombstones(&xml).is_empty(), "{}", xml);
+}
+
+#[test]
+fn keeps_chapters_unchanged_since_blame_since_alive() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 0);
+    commit_file(&repo, root, "src/old.md", "# Old\n", 1);
+    let sections = || vec![chapter("Guide", "guide.md", "# Guide\n\nMore.\n"), chapter("Old", "old.md", "# Old\n")];
+    atom_feed(root, json!({ "base_url": "https://example.com/book/", "tombstones": true }), sections());
+
+    // Old has no lines changed since then, so it's left out of the feed.
+    commit_file(&repo, root, "src/guide.md", "# Guide\n\nMore.\n", 10);
+    let options = json!({ "base_url": "https://example.com/book/", "tombstones": true, "blame_since": "2020-09-13T12:30:00Z" });
+    let xml = atom_feed(root, options, sections());
+
+    assert_eq!(entry_ids(&xml).len(), 1);
+    assert!(tombstones(&xml).is_empty(), "{}", xml);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use mdbook::book::BookItem;
+use mdbook::preprocess::{CmdPreprocessor, Preprocessor};
+use mdbook_git_atom::updated_processor::UpdatedProcessor;
+use serde_json::json;
+use common::{chapter, commit_file, run_atom_processor, CURRENT_DIRECTORY};
+
+fn translated_book() -> (tempfile::TempDir, Vec<serde_json::Value>) {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src/en")).unwrap();
+    fs::create_dir_all(root.join("src/de")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/en/guide.md", "# Guide\n\n{{#feed_url}}\n", 0);
+    commit_file(&repo, root, "src/de/guide.md", "# Anleitung\n\n{{#feed_url}}\n", 1);
+    let sections = vec![
+        chapter("Guide", "en/guide.md", "# Guide\n\n{{#feed_url}}\n"),
+        chapter("Anleitung", "de/guide.md", "# Anleitung\n\n{{#feed_url}}\n"),
+    ];
+    (directory, sections)
+}
+
+fn feed(path: &std::path::Path) -> atom_syndication::Feed {
+    fs::read_to_string(path).unwrap().parse().unwrap()
+}
+
+#[test]
+fn identifies_each_translation_by_its_own_feed_url() {
+    let (directory, sections) = translated_book();
+    let root = directory.path();
+
+    run_atom_processor(root, json!({ "base_url": "https://example.com/book/", "translations": ["en", "de"] }), sections);
+
+    assert_eq!(feed(&root.join("src/en/atom.xml")).id, "https://example.com/book/en/atom.xml");
+    assert_eq!(feed(&root.join("src/de/atom.xml")).id, "https://example.com/book/de/atom.xml");
+}
+
+#[test]
+fn links_translated_chapters_to_their_translations_feed() {
+    let (directory, sections) = translated_book();
+    let root = directory.path();
+    run_atom_processor(root, json!({ "base_url": "https://example.com/book/", "translations": ["en", "de"] }), sections.clone());
+
+    let input = json!([
+        {
+            "root": root,
+            "config": {
+                "book": { "title": "Test Book", "src": "src" },
+                "preprocessor": { "git-updated": { "translations": ["en", "de"] } },
+            },
+            "renderer": "html",
+            "mdbook_version": "0.4.21",
+        },
+        { "sections": sections, "__non_exhaustive": null },
+    ]);
+    let (ctx, book) = CmdPreprocessor::parse_input(input.to_string().as_bytes()).unwrap();
+    let book = {
+        let _guard = CURRENT_DIRECTORY.lock().unwrap_or_else(|error| error.into_inner());
+        std::env::set_current_dir(root).unwrap();
+        UpdatedProcessor::new().run(&ctx, book).unwrap()
+    };
+
+    let contents: Vec<String> = book.iter()
+        .filter_map(|item| match item {
+            BookItem::Chapter(chapter) => Some(chapter.content.to_string()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(contents, vec![
+        "# Guide\n\nhttps://example.com/book/en/atom.xml\n",
+        "# Anleitung\n\nhttps://example.com/book/de/atom.xml\n",
+    ]);
+}
@@ -0,0 +1,23 @@
+use mdbook_git_atom::library_helpers::{mdbook_version_mismatch, VersionMismatch};
+
+#[test]
+fn accepts_compatible_versions() {
+    assert_eq!(mdbook_version_mismatch("0.4.21", "0.4.21"), None);
+    assert_eq!(mdbook_version_mismatch("0.4.21", "0.4.37"), None);
+    assert_eq!(mdbook_version_mismatch("1.2.0", "1.5.3"), None);
+}
+
+#[test]
+fn warns_about_a_minor_mismatch() {
+    assert_eq!(mdbook_version_mismatch("0.4.21", "0.4.10"), Some(VersionMismatch::Minor));
+    assert_eq!(mdbook_version_mismatch("1.5.0", "1.2.0"), Some(VersionMismatch::Minor));
+    assert_eq!(mdbook_version_mismatch("0.4.21", "not a version"), Some(VersionMismatch::Minor));
+}
+
+#[test]
+fn fails_on_a_major_mismatch() {
+    // 0.4 and 0.5 aren't caret-compatible, so a new 0.x minor version is as breaking as a new major version.
+    assert_eq!(mdbook_version_mismatch("0.4.21", "0.5.0"), Some(VersionMismatch::Major));
+    assert_eq!(mdbook_version_mismatch("0.4.21", "0.3.9"), Some(VersionMismatch::Major));
+    assert_eq!(mdbook_version_mismatch("0.4.21", "1.0.0"), Some(VersionMismatch::Major));
+}
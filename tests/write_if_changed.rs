@@ -0,0 +1,84 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use git2::Repository;
+use mdbook_git_atom::library_helpers::write_if_changed;
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file};
+
+// A modification time far enough in the past that rewriting the file always changes it.
+fn make_old(path: &Path) -> SystemTime {
+    let old = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    fs::File::options().write(true).open(path).unwrap().set_modified(old).unwrap();
+    fs::metadata(path).unwrap().modified().unwrap()
+}
+
+#[test]
+fn leaves_an_unchanged_file_alone() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("atom.xml");
+    fs::write(&path, "<feed/>").unwrap();
+    let modified = make_old(&path);
+
+    assert!(!write_if_changed(&path, "<feed/>").unwrap());
+    assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+}
+
+#[test]
+fn rewrites_a_changed_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("atom.xml");
+    fs::write(&path, "<feed/>").unwrap();
+    let modified = make_old(&path);
+
+    assert!(write_if_changed(&path, "<feed><entry/></feed>").unwrap());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "<feed><entry/></feed>");
+    assert!(fs::metadata(&path).unwrap().modified().unwrap() > modified);
+}
+
+#[test]
+fn writes_a_missing_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("atom.xml");
+
+    assert!(write_if_changed(&path, "<feed/>").unwrap());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "<feed/>");
+}
+
+#[test]
+fn leaves_files_with_similar_names_alone() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("recent.html");
+    fs::write(directory.path().join("recent.tmp"), "Someone else's file").unwrap();
+
+    assert!(write_if_changed(&path, "<ul>\n</ul>\n").unwrap());
+
+    assert_eq!(fs::read_to_string(directory.path().join("recent.tmp")).unwrap(), "Someone else's file");
+    let mut file_names: Vec<String> = fs::read_dir(directory.path()).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    file_names.sort();
+    assert_eq!(file_names, ["recent.html", "recent.tmp"]);
+}
+
+#[test]
+fn rebuilding_an_unchanged_book_leaves_the_feed_alone() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide.md", "# Guide\n", 0);
+    let options = json!({ "base_url": "https://example.com/book/", "manifest": true });
+    let sections = || vec![chapter("Guide", "guide.md", "# Guide\n")];
+
+    let first_feed = atom_feed(root, options.clone(), sections());
+    let feed_modified = make_old(&root.join("src/atom.xml"));
+    let manifest_modified = make_old(&root.join("src/posts.json"));
+    let second_feed = atom_feed(root, options, sections());
+
+    assert_eq!(second_feed, first_feed);
+    assert_eq!(fs::metadata(root.join("src/atom.xml")).unwrap().modified().unwrap(), feed_modified);
+    assert_eq!(fs::metadata(root.join("src/posts.json")).unwrap().modified().unwrap(), manifest_modified);
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use std::fs;
+use git2::Repository;
+use serde_json::json;
+use common::{atom_feed, chapter, commit_file};
+
+fn feed_element(xml: &str) -> &str {
+    let start = xml.find("<feed ").unwrap();
+    &xml[start..start + xml[start..].find('>').unwrap()]
+}
+
+fn book_feed(options: serde_json::Value) -> String {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src/guide")).unwrap();
+    let repo = Repository::init(root).unwrap();
+    commit_file(&repo, root, "src/guide/install.md", "# Install\n\n![Screenshot](screenshot.png)\n", 0);
+
+    atom_feed(root, options, vec![chapter("Install", "guide/install.md", "# Install\n\n![Screenshot](screenshot.png)\n")])
+}
+
+#[test]
+fn sets_the_base_url_as_the_base_of_the_feed_and_content() {
+    let xml = book_feed(json!({ "base_url": "https://example.com/book/", "xml_base": true, "preview_lines": -1 }));
+
+    assert!(feed_element(&xml).contains(" xml:base=\"https://example.com/book/\""), "{}", xml);
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+    let content = feed.entries[0].content.as_ref().unwrap();
+    assert_eq!(content.base.as_deref(), Some("https://example.com/book/"));
+}
+
+#[test]
+fn leaves_out_the_base_by_default() {
+    let xml = book_feed(json!({ "base_url": "https://example.com/book/", "preview_lines": -1 }));
+
+    assert!(!feed_element(&xml).contains("xml:base"), "{}", xml);
+    let feed = xml.parse::<atom_syndication::Feed>().unwrap();
+    assert_eq!(feed.entries[0].content.as_ref().unwrap().base, None);
+}